@@ -0,0 +1,231 @@
+//! File-based queue of risky commands waiting for a human decision.
+//!
+//! Each request is a JSON file in an `approvals` directory next to the
+//! session file. A reviewer approves or denies a request with the server's
+//! CLI, and the waiting server picks the change up.
+//!
+//! The directory and its files are readable and writable only by their
+//! owner. The server also keeps the requests it submitted in memory and only
+//! runs one that still matches, which means a request can't be run by a
+//! server other than the one that made it, or run twice.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use parking_lot::Mutex;
+use tokio::{fs, io::AsyncWriteExt};
+use crate::{
+    error::ClientError,
+    types::{ApprovalRequest, ApprovalStatus},
+};
+
+/// How often a waiting request's file is checked for a decision
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+pub struct ApprovalQueue {
+    dir: PathBuf,
+    /// Requests submitted through this queue, as they were submitted, or
+    /// `None` once one has been claimed to run
+    submitted: Arc<Mutex<HashMap<u64, Option<ApprovalRequest>>>>,
+}
+
+impl ApprovalQueue {
+    pub fn new(session_file: &Path) -> Self {
+        let dir = session_file.parent().unwrap_or(Path::new(".")).join("approvals");
+        Self { dir, submitted: Arc::default() }
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Queue a request as pending, giving it the next free id
+    pub async fn submit(
+        &self,
+        session_id: &str,
+        project: &str,
+        command: &str,
+        cwd: &Path,
+        timeout_ms: Option<u64>,
+        stdin: Option<&str>,
+    ) -> Result<ApprovalRequest, ClientError> {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(&self.dir).await?;
+
+        let mut request = ApprovalRequest {
+            id: 0,
+            session_id: session_id.to_string(),
+            project: project.to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_path_buf(),
+            timeout_ms,
+            stdin: stdin.map(str::to_string),
+            requested_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            status: ApprovalStatus::Pending,
+            reason: None,
+        };
+
+        // Claim an id by creating its file, so other servers sharing the
+        // queue can't take the same one
+        let mut id = self.list().await?.last().map_or(1, |r| r.id + 1);
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(self.path(id)).await {
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => id += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        request.id = id;
+        self.save(&request).await?;
+        self.submitted.lock().insert(id, Some(request.clone()));
+        Ok(request)
+    }
+
+    pub async fn get(&self, id: u64) -> Result<ApprovalRequest, ClientError> {
+        let contents = match fs::read_to_string(self.path(id)).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(ClientError::ApprovalNotFound { id }),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    }
+
+    /// All requests, oldest first
+    pub async fn list(&self) -> Result<Vec<ApprovalRequest>, ClientError> {
+        let mut entries = match fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut requests = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let Some(id) = entry.path()
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            else {
+                continue;
+            };
+            // Skips ids that are claimed but not yet written
+            if let Ok(request) = self.get(id).await {
+                requests.push(request);
+            }
+        }
+        requests.sort_by_key(|r| r.id);
+        Ok(requests)
+    }
+
+    /// Record a reviewer's decision on a pending request
+    pub async fn decide(&self, id: u64, approved: bool, reason: Option<&str>) -> Result<ApprovalRequest, ClientError> {
+        let mut request = self.get(id).await?;
+        if request.status != ApprovalStatus::Pending {
+            return Err(ClientError::ApprovalNotPending { id, status: request.status });
+        }
+        request.status = if approved { ApprovalStatus::Approved } else { ApprovalStatus::Denied };
+        request.reason = reason.map(str::to_string);
+        self.save(&request).await?;
+        Ok(request)
+    }
+
+    /// Take an approved request for running. Fails if it doesn't hold what
+    /// this queue submitted, or if it has already been taken, so however
+    /// many callers race for it only one runs it.
+    pub async fn claim(&self, request: ApprovalRequest) -> Result<ApprovalRequest, ClientError> {
+        {
+            let mut submitted = self.submitted.lock();
+            let Some(entry) = submitted.get_mut(&request.id) else {
+                return Err(ClientError::ApprovalInvalid { id: request.id });
+            };
+            let Some(original) = entry.as_ref() else {
+                return Err(ClientError::ApprovalNotPending { id: request.id, status: ApprovalStatus::Ran });
+            };
+            let matches = (&request.session_id, &request.project, &request.command, &request.cwd, request.timeout_ms, &request.stdin)
+                == (&original.session_id, &original.project, &original.command, &original.cwd, original.timeout_ms, &original.stdin);
+            if !matches {
+                return Err(ClientError::ApprovalInvalid { id: request.id });
+            }
+            *entry = None;
+        }
+
+        let request = ApprovalRequest { status: ApprovalStatus::Ran, ..request };
+        self.save(&request).await?;
+        Ok(request)
+    }
+
+    /// Wait up to `timeout` for a pending request to be decided, returning it
+    /// in whatever state it's in by then
+    pub async fn wait(&self, id: u64, timeout: Duration) -> Result<ApprovalRequest, ClientError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let request = self.get(id).await?;
+            if request.status != ApprovalStatus::Pending || Instant::now() >= deadline {
+                return Ok(request);
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+    }
+
+    /// Replace the request's file in one step, so readers never see it half written
+    async fn save(&self, request: &ApprovalRequest) -> Result<(), ClientError> {
+        let json = serde_json::to_string_pretty(request).map_err(io::Error::other)?;
+        let temp = self.dir.join(format!(".{}.json.tmp", request.id));
+        let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&temp).await?;
+        file.write_all(json.as_bytes()).await?;
+        fs::rename(&temp, self.path(request.id)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn submit(queue: &ApprovalQueue, command: &str) -> ApprovalRequest {
+        queue.submit("a-session", "test", command, Path::new("/project"), None, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_decide() {
+        let dir = TempDir::new().unwrap();
+        let queue = ApprovalQueue::new(&dir.path().join("session.toml"));
+
+        assert_eq!(submit(&queue, "rm -rf target").await.id, 1);
+        assert_eq!(submit(&queue, "git push").await.id, 2);
+
+        let denied = queue.decide(2, false, Some("not yet")).await.unwrap();
+        assert_eq!((denied.status, denied.reason.as_deref()), (ApprovalStatus::Denied, Some("not yet")));
+        assert!(matches!(queue.decide(2, true, None).await, Err(ClientError::ApprovalNotPending { id: 2, .. })));
+        assert!(matches!(queue.get(3).await, Err(ClientError::ApprovalNotFound { id: 3 })));
+
+        let statuses: Vec<_> = queue.list().await.unwrap().iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![ApprovalStatus::Pending, ApprovalStatus::Denied]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_sees_decision_from_another_queue() {
+        let dir = TempDir::new().unwrap();
+        let queue = ApprovalQueue::new(&dir.path().join("session.toml"));
+        let request = submit(&queue, "make deploy").await;
+
+        let pending = queue.wait(request.id, Duration::from_millis(50)).await.unwrap();
+        assert_eq!(pending.status, ApprovalStatus::Pending);
+
+        // The reviewer's CLI uses its own queue on the same directory
+        let reviewer = ApprovalQueue::new(&dir.path().join("session.toml"));
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            reviewer.decide(request.id, true, None).await.unwrap();
+        });
+        let approved = queue.wait(request.id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(approved.status, ApprovalStatus::Approved);
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+use codem_core::types::OutputSender;
+use crate::{
+    client::command::{command_result, run_in_session},
+    error::ClientError,
+    history::RunTimer,
+    session::manager::session::Session,
+    types::{ApprovalRequest, ApprovalStatus, CommandResult, RunKind},
+};
+
+impl crate::Client {
+    /// Wait for a decision on a risky command queued for approval, and run it
+    /// once approved.
+    ///
+    /// Fails with `ApprovalPending` if it is still undecided after the
+    /// project's `approval.wait_ms`.
+    pub async fn await_approval(
+        &self,
+        session_id: &str,
+        approval_id: u64,
+        output: Option<OutputSender>,
+    ) -> Result<CommandResult, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+        self.run_when_approved(&session, approval_id, output).await
+    }
+
+    /// Approval requests made by the session, oldest first
+    pub async fn list_approvals(&self, session_id: &str) -> Result<Vec<ApprovalRequest>, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        Ok(self.approvals.list().await?
+            .into_iter()
+            .filter(|r| r.session_id == session_id)
+            .collect())
+    }
+
+    pub(crate) async fn run_when_approved(
+        &self,
+        session: &Session,
+        approval_id: u64,
+        output: Option<OutputSender>,
+    ) -> Result<CommandResult, ClientError> {
+        // Other sessions' requests are treated as missing
+        let request = self.approvals.get(approval_id).await?;
+        if request.session_id != session.id {
+            return Err(ClientError::ApprovalNotFound { id: approval_id });
+        }
+
+        let wait = Duration::from_millis(session.project.approval.wait_ms);
+        let request = self.approvals.wait(approval_id, wait).await?;
+        let timer = RunTimer::start(RunKind::CommandRisky, &request.command, &request.cwd);
+
+        match request.status {
+            ApprovalStatus::Pending => Err(ClientError::ApprovalPending { id: approval_id }),
+            ApprovalStatus::Ran => Err(ClientError::ApprovalNotPending { id: approval_id, status: request.status }),
+            ApprovalStatus::Denied => {
+                let err = ClientError::CommandDenied { id: approval_id, reason: request.reason };
+                self.record_run(session, timer.finish_error(&err)).await;
+                Err(err)
+            }
+            ApprovalStatus::Approved => {
+                // Run what was submitted and approved, not whatever the file says now
+                let request = match self.approvals.claim(request).await {
+                    Ok(request) => request,
+                    Err(err) => {
+                        self.record_run(session, timer.finish_error(&err)).await;
+                        return Err(err);
+                    }
+                };
+                let result = run_in_session(
+                    session,
+                    &request.command,
+                    &request.cwd,
+                    request.timeout_ms,
+                    request.stdin.as_deref(),
+                    output,
+                ).await;
+                self.record_run(session, timer.finish_command(result.0.as_ref())).await;
+                command_result(session, result)
+            }
+        }
+    }
+}
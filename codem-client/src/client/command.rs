@@ -1,6 +1,21 @@
-use std::path::Path;
-use codem_core::command::run_command;
-use crate::error::ClientError;
+use std::path::{Path, PathBuf};
+use codem_core::{
+    command::run_command_with_options,
+    redact::Redactor,
+    types::{CommandOptions, CommandOutput, OutputSender, ResourceLimit},
+    CommandError,
+};
+use std::time::SystemTime;
+use crate::{
+    error::ClientError,
+    history::RunTimer,
+    impact::select_tests,
+    project::Project,
+    session::manager::{path::normalize, session::Session},
+    test_cache::fingerprint,
+    test_results::{parse_test_output, JunitParser, TestParser},
+    types::{CommandResult, CommandRun, RunKind, TestReport, TestRun, TestSelection},
+};
 
 impl crate::Client {
     pub async fn run_command(
@@ -8,48 +23,26 @@ impl crate::Client {
         session_id: &str,
         command: &str,
         cwd: Option<&Path>,
-        timeout: Option<u64>
-    ) -> Result<String, ClientError> {
+        timeout: Option<u64>,
+        stdin: Option<&str>,
+        output: Option<OutputSender>,
+    ) -> Result<CommandResult, ClientError> {
         let session = self.sessions.get_session(session_id).await?;
         
-        // cwd is project base_path
-        let cwd = cwd.or(Some(session.project.base_path.as_path()));
+        let cwd = resolve_cwd(&session, cwd)?;
+        let timer = RunTimer::start(RunKind::Command, command, &cwd);
 
         // Check command safety and raise if unsafe
-        let is_safe = self.sessions.config().is_command_safe(command);
-        if !is_safe {
-            return Err(ClientError::UnsafeCommand { command: command.to_string() });
-        }
-
-        let output = run_command(
-            command,
-            cwd,
-            timeout
-        ).await?;
-
-        if output.exit_code != 0 {
-            return Err(ClientError::CommandError(
-                codem_core::error::CommandError::CommandFailed { 
-                    stdout: output.stdout,
-                    stderr: output.stderr,
-                    exit_code: output.exit_code 
-                }
-            ));
-        }
-        
-        // Combine stdout and stderr
-        let mut combined = String::new();
-        if !output.stdout.is_empty() {
-            combined.push_str(&output.stdout);
-        }
-        if !output.stderr.is_empty() {
-            if !combined.is_empty() {
-                combined.push('\n');
-            }
-            combined.push_str(&output.stderr);
-        }
+        let result = match self.sessions.config().check_project_command(&session.project, command) {
+            Err(reason) => (Err(ClientError::UnsafeCommand {
+                command: command.to_string(),
+                reason: reason.to_string(),
+            }), Vec::new()),
+            Ok(()) => run_in_session(&session, command, &cwd, timeout, stdin, output).await,
+        };
 
-        Ok(combined)
+        self.record_run(&session, timer.finish_command(result.0.as_ref())).await;
+        command_result(&session, result)
     }
 
     pub async fn run_command_risky(
@@ -57,79 +50,367 @@ impl crate::Client {
         session_id: &str,
         command: &str,
         cwd: Option<&Path>,
-        timeout: Option<u64>
-    ) -> Result<String, ClientError> {
+        timeout: Option<u64>,
+        stdin: Option<&str>,
+        output: Option<OutputSender>,
+    ) -> Result<CommandResult, ClientError> {
         let session = self.sessions.get_session(session_id).await?;
         
-        // cwd is project base_path
-        let cwd = cwd.or(Some(session.project.base_path.as_path()));
+        let cwd = resolve_cwd(&session, cwd)?;
 
-        // No safety checks - can run any command
-
-        let output = run_command(
-            command,
-            cwd,
-            timeout
-        ).await?;
-
-        if output.exit_code != 0 {
-            return Err(ClientError::CommandError(
-                codem_core::error::CommandError::CommandFailed { 
-                    stdout: output.stdout,
-                    stderr: output.stderr,
-                    exit_code: output.exit_code 
-                }
-            ));
-        }
-        
-        // Combine stdout and stderr
-        let mut combined = String::new();
-        if !output.stdout.is_empty() {
-            combined.push_str(&output.stdout);
-        }
-        if !output.stderr.is_empty() {
-            if !combined.is_empty() {
-                combined.push('\n');
-            }
-            combined.push_str(&output.stderr);
+        if session.project.approval.required {
+            let request = self.approvals
+                .submit(&session.id, &session.project.name, command, &cwd, timeout, stdin)
+                .await?;
+            return self.run_when_approved(&session, request.id, output).await;
         }
 
-        Ok(combined)
+        let timer = RunTimer::start(RunKind::CommandRisky, command, &cwd);
+
+        // No safety checks - can run any command
+        let result = run_in_session(&session, command, &cwd, timeout, stdin, output).await;
+
+        self.record_run(&session, timer.finish_command(result.0.as_ref())).await;
+        command_result(&session, result)
     }
 
+    /// Run the project's test command and parse its results.
+    ///
+    /// Fails with `TestCommandFailed` if the command exits non-zero; the
+    /// parsed results are included there as well. With `no_cache`, the tests
+    /// run even if the project hasn't changed since they last did.
     pub async fn run_test_command(
         &self,
         session_id: &str,
-    ) -> Result<String, ClientError> {
+        no_cache: bool,
+        output: Option<OutputSender>,
+    ) -> Result<TestRun, ClientError> {
         // Validate session exists and get project
         let session = self.sessions.get_session(session_id).await?;
-
         // Test command is in project config
         let test_command = session.project.test_command
             .as_ref()
             .ok_or(ClientError::TestCommandNotConfigured)?;
+        self.run_tests(&session, test_command, no_cache, output).await
+    }
 
-        // cwd is project base_path
-        let cwd = Some(session.project.base_path.as_path());
+    /// Choose the tests affected by the files written in the session, or
+    /// the full suite if they can't be determined
+    pub async fn select_affected_tests(&self, session_id: &str) -> Result<TestSelection, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+        select_tests(&session.project, &session.written_files().await).await
+    }
 
-        let output = run_command(test_command, cwd, None).await?;
+    /// Run the tests chosen by `select_affected_tests` and parse their results
+    pub async fn run_test_selection(
+        &self,
+        session_id: &str,
+        selection: &TestSelection,
+        no_cache: bool,
+        output: Option<OutputSender>,
+    ) -> Result<TestRun, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+        self.run_tests(&session, &selection.command, no_cache, output).await
+    }
+
+    /// Commands run in the session, oldest first
+    pub async fn command_history(&self, session_id: &str) -> Result<Vec<CommandRun>, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        self.history.list(session_id).await
+    }
+
+    pub async fn command_run(&self, session_id: &str, run_id: u64) -> Result<CommandRun, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        self.history.get(session_id, run_id).await
+    }
+
+    /// Run a test command, reusing the cached result if none of the files the
+    /// session has read or written have changed since it last ran, and record
+    /// it in the session's history
+    pub(crate) async fn run_tests(
+        &self,
+        session: &Session,
+        test_command: &str,
+        no_cache: bool,
+        output: Option<OutputSender>,
+    ) -> Result<TestRun, ClientError> {
+        let project = &session.project;
+        let timer = RunTimer::start(RunKind::Test, test_command, &project.base_path);
+        let cached = if no_cache {
+            None
+        } else {
+            self.test_cache.get(project, test_command, fingerprint(session).await)
+        };
 
-        if output.exit_code != 0 {
-            return Err(ClientError::TestCommandFailed { stdout: output.stdout, stderr: output.stderr, exit_code: output.exit_code });
+        let result = match cached {
+            Some(cached) => cached,
+            None => {
+                let ran_at = SystemTime::now();
+                let result = run_project_tests(project, test_command, output).await;
+                // Taken after the run, since the tests may have written files of their own
+                self.test_cache.store(project, test_command, fingerprint(session).await, ran_at, &result);
+                result
+            }
+        };
+
+        let run = match &result {
+            Ok(run) => timer.finish(Some(run.exit_code), run.output.clone(), None),
+            Err(ClientError::TestCommandFailed { stdout, stderr, exit_code, .. }) => {
+                let output = CommandOutput {
+                    stdout: stdout.clone(),
+                    stderr: stderr.clone(),
+                    exit_code: *exit_code,
+                    limits_hit: Vec::new(),
+                };
+                timer.finish(Some(*exit_code), combine_output(&output), None)
+            }
+            Err(e) => timer.finish_error(e),
+        };
+        let cached_at = match &result {
+            Ok(run) => run.cached_at,
+            Err(ClientError::TestCommandFailed { cached_at, .. }) => *cached_at,
+            Err(_) => None,
+        };
+        self.record_run(session, CommandRun { cached_at, ..run }).await;
+
+        result
+    }
+
+    /// Add a run to the session's history. Failing to record it doesn't fail the command.
+    pub(crate) async fn record_run(&self, session: &Session, mut run: CommandRun) {
+        // Keep secrets on the command line out of the history file
+        if let Ok(Some(redactor)) = Redactor::new(&session.project.secrets, &session.project.secret_patterns) {
+            run.command = redactor.redact(&run.command).into_owned();
         }
-        
-        // Combine stdout and stderr
-        let mut combined = String::new();
-        if !output.stdout.is_empty() {
-            combined.push_str(&output.stdout);
+        if let Err(e) = self.history.append(&session.id, run).await {
+            tracing::warn!("Failed to record command in history of session {}: {}", session.id, e);
+        }
+    }
+}
+
+impl RunTimer {
+    pub(crate) fn finish_command(self, result: Result<&CommandOutput, &ClientError>) -> CommandRun {
+        match result {
+            Ok(output) => self.finish(Some(output.exit_code), combine_output(output), None),
+            Err(e) => self.finish_error(e),
+        }
+    }
+
+    pub(crate) fn finish_error(self, error: &ClientError) -> CommandRun {
+        match error {
+            // Keep what the command printed before it was stopped
+            ClientError::CommandError(CommandError::Timeout { timeout_ms, output, .. }) => {
+                self.finish(None, combine_output(output), Some(format!("timed out after {}ms", timeout_ms)))
+            }
+            ClientError::CommandError(CommandError::LimitExceeded { limit, output, .. }) => {
+                self.finish(None, combine_output(output), Some(format!("killed after exceeding its {}", limit)))
+            }
+            e => self.finish(None, String::new(), Some(e.to_string())),
+        }
+    }
+}
+
+/// Resolve a command's working directory against the project's base path
+/// (the default), making sure it doesn't lead out of the session's project.
+///
+/// The path is checked with the session's `PathValidator` before anything
+/// on disk is looked at, so a path out of scope is rejected whether or not
+/// it exists. Symlinks are then resolved and checked again, so a link
+/// inside the project can't be used to run commands elsewhere.
+pub(crate) fn resolve_cwd(session: &Session, cwd: Option<&Path>) -> Result<PathBuf, ClientError> {
+    let project = &session.project;
+    let cwd = match cwd {
+        Some(cwd) => normalize(&project.base_path.join(cwd)),
+        None => project.base_path.clone(),
+    };
+    session.validate_path(&cwd)?;
+    // The validator accepts any configured project; commands stay in this one
+    if !in_project(project, &cwd) {
+        return Err(ClientError::PathOutOfScope { path: cwd });
+    }
+
+    let resolved = cwd.canonicalize().map_err(|_| ClientError::FileNotFound { path: cwd })?;
+    if !in_project(project, &resolved) {
+        return Err(ClientError::PathOutOfScope { path: resolved });
+    }
+    Ok(resolved)
+}
+
+/// Whether a path is under the project's base path or one of its allowed paths,
+/// as given or with symlinks resolved
+fn in_project(project: &Project, path: &Path) -> bool {
+    std::iter::once(&project.base_path)
+        .chain(project.allowed_paths.iter().flatten())
+        .any(|root| path.starts_with(root) || root.canonicalize().is_ok_and(|root| path.starts_with(root)))
+}
+
+/// Run a command for a session, returning its output and the files the
+/// session has read that it changed. The files are checked even if the
+/// command fails or is stopped.
+pub(crate) async fn run_in_session(
+    session: &Session,
+    command: &str,
+    cwd: &Path,
+    timeout: Option<u64>,
+    stdin: Option<&str>,
+    output: Option<OutputSender>,
+) -> (Result<CommandOutput, ClientError>, Vec<PathBuf>) {
+    let options = match session.project.command_options(cwd) {
+        Ok(options) => CommandOptions {
+            timeout_ms: timeout,
+            stdin: stdin.map(str::to_string),
+            output,
+            ..options
+        },
+        Err(e) => return (Err(e.into()), Vec::new()),
+    };
+
+    let tracked = session.tracked_files().await;
+    let before = modified_times(&tracked).await;
+    let mut result = run_command_with_options(command, &options).await.map_err(ClientError::from);
+    let after = modified_times(&tracked).await;
+
+    let mut changed = Vec::new();
+    for ((path, before), after) in tracked.into_iter().zip(before).zip(after) {
+        if before == after {
+            continue;
         }
-        if !output.stderr.is_empty() {
-            if !combined.is_empty() {
-                combined.push('\n');
+        if let (Some(modified), true) = (after, session.project.refresh_changed_files) {
+            if let Err(e) = session.update_timestamp(&path, modified).await {
+                result = result.and(Err(e));
             }
-            combined.push_str(&output.stderr);
         }
+        changed.push(path);
+    }
+    changed.sort();
+
+    (result, changed)
+}
+
+async fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    let mut times = Vec::with_capacity(paths.len());
+    for path in paths {
+        times.push(tokio::fs::metadata(path).await.and_then(|m| m.modified()).ok());
+    }
+    times
+}
 
-        Ok(combined)
+/// The result of a command that exited successfully.
+///
+/// A command that fails, times out or is killed after changing files the
+/// session has read fails with `CommandFailedWithChanges`, so the changes
+/// aren't lost with the output.
+pub(crate) fn command_result(
+    session: &Session,
+    (result, changed_files): (Result<CommandOutput, ClientError>, Vec<PathBuf>),
+) -> Result<CommandResult, ClientError> {
+    let base_path = &session.project.base_path;
+    let changed_files: Vec<_> = changed_files.iter()
+        .map(|path| path.strip_prefix(base_path).unwrap_or(path).to_path_buf())
+        .collect();
+    let timestamps_refreshed = session.project.refresh_changed_files;
+
+    let error = match result {
+        Ok(output) if output.exit_code == 0 => {
+            return Ok(CommandResult {
+                output: combine_output(&output),
+                changed_files,
+                timestamps_refreshed,
+            });
+        }
+        Ok(output) => CommandError::CommandFailed {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: output.exit_code
+        },
+        Err(ClientError::CommandError(error)) => error,
+        Err(e) => return Err(e),
+    };
+
+    if changed_files.is_empty() {
+        return Err(ClientError::CommandError(error));
+    }
+    Err(ClientError::CommandFailedWithChanges { error, changed_files, timestamps_refreshed })
+}
+
+/// Run a test command for the project and parse its results
+pub(crate) async fn run_project_tests(
+    project: &Project,
+    test_command: &str,
+    output: Option<OutputSender>,
+) -> Result<TestRun, ClientError> {
+    // cwd is project base_path
+    let options = CommandOptions {
+        output,
+        ..project.command_options(&project.base_path)?
+    };
+
+    let report_path = project.test_report_path.as_ref().map(|path| project.base_path.join(path));
+    let previous_report = report_path.as_deref().and_then(modified_time);
+    let output = run_command_with_options(test_command, &options).await?;
+    let combined = combine_output(&output);
+
+    let report = match &report_path {
+        Some(path) => read_test_report(path, previous_report, options.redactor.as_deref()),
+        None => parse_test_output(project.test_format, &combined),
+    };
+
+    if output.exit_code != 0 {
+        return Err(ClientError::TestCommandFailed {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: output.exit_code,
+            report: report.map(Box::new),
+            cached_at: None,
+        });
+    }
+
+    Ok(TestRun {
+        output: combined,
+        exit_code: output.exit_code,
+        report,
+        cached_at: None,
+    })
+}
+
+/// Parse a JUnit report written by the test command, ignoring one left over
+/// from an earlier run. Unlike the command's output, the file hasn't been
+/// redacted yet.
+fn read_test_report(path: &Path, previous: Option<SystemTime>, redactor: Option<&Redactor>) -> Option<TestReport> {
+    // File timestamps are coarser than the system clock, so compare with the
+    // report's own timestamp from before the run rather than the start time
+    let modified = modified_time(path)?;
+    if previous.is_some_and(|previous| modified <= previous) {
+        return None;
+    }
+    let mut report = JunitParser.parse(&std::fs::read_to_string(path).ok()?)?;
+    if let Some(redactor) = redactor {
+        report.redact(redactor);
+    }
+    Some(report)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Combine stdout and stderr into the text returned to the caller
+pub(crate) fn combine_output(output: &CommandOutput) -> String {
+    let mut combined = String::new();
+    if !output.stdout.is_empty() {
+        combined.push_str(&output.stdout);
+    }
+    if !output.stderr.is_empty() {
+        if !combined.is_empty() {
+            combined.push('\n');
+        }
+        combined.push_str(&output.stderr);
+    }
+    if output.limits_hit.contains(&ResourceLimit::Output) {
+        if !combined.is_empty() && !combined.ends_with('\n') {
+            combined.push('\n');
+        }
+        combined.push_str("[output truncated: output size limit reached]");
     }
-}
\ No newline at end of file
+    combined
+}
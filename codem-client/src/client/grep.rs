@@ -0,0 +1,172 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+use codem_core::{
+    error::DirectoryError,
+    types::{GrepCodebaseResult, GrepMatch, GrepOptions, PathFilter},
+};
+use crate::{
+    error::{grep_error::Pattern, ClientError, GrepError},
+    types::{CappedFile, GrepPaging, GrepPage},
+};
+
+impl crate::Client {
+    /// Search the codebase and return one page of the results
+    pub async fn grep_codebase_page(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+        pattern: &str,
+        options: &GrepOptions,
+        paging: &GrepPaging,
+    ) -> Result<GrepPage, ClientError> {
+        let results = self.grep_codebase(session_id, path, pattern, options).await?;
+        paginate(results, paging, search_id(path, pattern, options, paging))
+    }
+}
+
+/// Identifies a search in its cursors, so a cursor can't be used to page
+/// through a different one. Page sizes can change between pages; the
+/// per-file cap can't, since it decides which matches there are.
+fn search_id(path: Option<&Path>, pattern: &str, options: &GrepOptions, paging: &GrepPaging) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    pattern.hash(&mut hasher);
+    options.hash(&mut hasher);
+    paging.max_matches_per_file.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Make a filter's globs relative to the project, checking they're valid
+pub(crate) fn project_path_filter(filter: Option<&PathFilter>, base_path: &Path) -> Result<Option<PathFilter>, DirectoryError> {
+    let Some(filter) = filter else {
+        return Ok(None);
+    };
+    let filter = PathFilter {
+        base_path: base_path.to_path_buf(),
+        ..filter.clone()
+    };
+    filter.overrides().map_err(DirectoryError::GlobError)?;
+    Ok(Some(filter))
+}
+
+/// A bad glob in a search is reported like a bad search pattern
+pub(crate) fn grep_path_filter(filter: Option<&PathFilter>, base_path: &Path) -> Result<Option<PathFilter>, ClientError> {
+    project_path_filter(filter, base_path)
+        .map_err(|e| GrepError::InvalidPattern(Pattern(e.to_string())).into())
+}
+
+/// Position of the next result: the search it belongs to, a file's index in
+/// the sorted results and a match block within it
+fn parse_cursor(cursor: &str, search: &str) -> Result<(usize, usize), ClientError> {
+    let mut parts = cursor.splitn(3, ':');
+    let (Some(id), Some(file), Some(block)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(GrepError::InvalidCursor { cursor: cursor.to_string() }.into());
+    };
+    let (Ok(file), Ok(block)) = (file.parse(), block.parse()) else {
+        return Err(GrepError::InvalidCursor { cursor: cursor.to_string() }.into());
+    };
+    if id != search {
+        return Err(GrepError::CursorMismatch { cursor: cursor.to_string() }.into());
+    }
+    Ok((file, block))
+}
+
+fn match_count(blocks: &[GrepMatch]) -> usize {
+    blocks.iter().map(|m| m.match_lines.len()).sum()
+}
+
+/// Number of blocks that fit in `limit` matches. With `at_least_one`, the
+/// first block is taken even if it doesn't fit, so paging always progresses.
+fn blocks_within(blocks: &[GrepMatch], limit: Option<usize>, at_least_one: bool) -> usize {
+    let Some(limit) = limit else {
+        return blocks.len();
+    };
+    let mut count = 0;
+    let mut taken = 0;
+    for block in blocks {
+        count += block.match_lines.len();
+        if count > limit && !(taken == 0 && at_least_one) {
+            break;
+        }
+        taken += 1;
+    }
+    taken
+}
+
+/// Cut sorted results down to the requested page
+fn paginate(results: GrepCodebaseResult, paging: &GrepPaging, search: String) -> Result<GrepPage, ClientError> {
+    // A page that can't hold anything would hand back its own cursor
+    for (name, limit) in [
+        ("max_matches", paging.max_matches),
+        ("max_files", paging.max_files),
+        ("max_matches_per_file", paging.max_matches_per_file),
+    ] {
+        if limit == Some(0) {
+            return Err(GrepError::InvalidLimit { name: name.to_string() }.into());
+        }
+    }
+    let (start_file, start_block) = paging.cursor.as_deref()
+        .map(|cursor| parse_cursor(cursor, &search))
+        .transpose()?
+        .unwrap_or((0, 0));
+    let cursor = |file: usize, block: usize| format!("{}:{}:{}", search, file, block);
+    let files = results.files;
+
+    let mut page = GrepPage {
+        files: Vec::new(),
+        total_files: files.len(),
+        total_matches: files.iter().map(|file| match_count(&file.matches)).sum(),
+        capped_files: Vec::new(),
+        next_cursor: None,
+        // Reported once, with the first page
+        errors: if paging.cursor.is_none() { results.errors } else { Vec::new() },
+    };
+    let mut page_matches = 0;
+
+    for (index, mut file) in files.into_iter().enumerate().skip(start_file) {
+        let file_matches = match_count(&file.matches);
+        file.matches.truncate(blocks_within(&file.matches, paging.max_matches_per_file, true));
+        let capped = match_count(&file.matches) < file_matches;
+
+        let first_block = if index == start_file { start_block } else { 0 };
+        // Binary files have no blocks but still take a place on a page
+        if first_block >= file.matches.len() && !(file.binary && first_block == 0) {
+            continue;
+        }
+        let here = cursor(index, first_block);
+
+        if paging.max_files.is_some_and(|max| page.files.len() >= max) {
+            page.next_cursor = Some(here);
+            break;
+        }
+        if file.binary {
+            page.files.push(file);
+            continue;
+        }
+
+        let remaining = paging.max_matches.map(|max| max.saturating_sub(page_matches));
+        let end = first_block + blocks_within(&file.matches[first_block..], remaining, page.files.is_empty());
+        if end == first_block {
+            page.next_cursor = Some(here);
+            break;
+        }
+        if end < file.matches.len() {
+            page.next_cursor = Some(cursor(index, end));
+        }
+
+        if capped {
+            page.capped_files.push(CappedFile { path: file.path.clone(), total_matches: file_matches });
+        }
+        page_matches += match_count(&file.matches[first_block..end]);
+        file.matches = file.matches.drain(first_block..end).collect();
+        page.files.push(file);
+
+        if page.next_cursor.is_some() {
+            break;
+        }
+    }
+
+    Ok(page)
+}
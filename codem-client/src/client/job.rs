@@ -0,0 +1,81 @@
+use std::path::Path;
+use crate::{
+    client::command::resolve_cwd,
+    error::ClientError,
+    session::manager::session::Session,
+    types::{JobInfo, JobOutput},
+};
+
+impl crate::Client {
+    /// Start a safe command in the background and return immediately
+    pub async fn start_job(
+        &self,
+        session_id: &str,
+        command: &str,
+        cwd: Option<&Path>,
+    ) -> Result<JobInfo, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+
+        // Check command safety and raise if unsafe
+        if let Err(reason) = self.sessions.config().check_project_command(&session.project, command) {
+            return Err(ClientError::UnsafeCommand {
+                command: command.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+
+        self.spawn_job(&session, command, cwd)
+    }
+
+    /// Start any command in the background and return immediately.
+    ///
+    /// Refused in projects that require approval for risky commands.
+    pub async fn start_job_risky(
+        &self,
+        session_id: &str,
+        command: &str,
+        cwd: Option<&Path>,
+    ) -> Result<JobInfo, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+
+        if session.project.approval.required {
+            return Err(ClientError::JobNeedsApproval { command: command.to_string() });
+        }
+
+        self.spawn_job(&session, command, cwd)
+    }
+
+    fn spawn_job(&self, session: &Session, command: &str, cwd: Option<&Path>) -> Result<JobInfo, ClientError> {
+        let cwd = resolve_cwd(session, cwd)?;
+
+        let options = session.project.command_options(&cwd)?;
+
+        Ok(self.jobs.start(&session.id, command, options))
+    }
+
+    pub async fn job_status(&self, session_id: &str, job_id: &str) -> Result<JobInfo, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        self.jobs.status(session_id, job_id)
+    }
+
+    pub async fn list_jobs(&self, session_id: &str) -> Result<Vec<JobInfo>, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        Ok(self.jobs.list(session_id))
+    }
+
+    /// Get output produced since the last tail, or since `offset` if given
+    pub async fn tail_job(
+        &self,
+        session_id: &str,
+        job_id: &str,
+        offset: Option<usize>,
+    ) -> Result<JobOutput, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        self.jobs.tail(session_id, job_id, offset)
+    }
+
+    pub async fn kill_job(&self, session_id: &str, job_id: &str) -> Result<JobInfo, ClientError> {
+        self.sessions.get_session(session_id).await?;
+        self.jobs.kill(session_id, job_id)
+    }
+}
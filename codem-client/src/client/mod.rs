@@ -1,20 +1,39 @@
+pub mod approval;
 pub mod command;
+pub mod grep;
+pub mod job;
 pub mod read;
+pub mod replace;
 pub mod write;
 
 use crate::{error::ClientError, config::ClientConfig};
+use crate::approval::ApprovalQueue;
+use crate::history::CommandHistory;
+use crate::jobs::JobManager;
+use crate::test_cache::TestCache;
+use replace::ReplacePreviews;
 use crate::session::manager::SessionManager;
-use codem_core::types::{GrepFileMatch, GrepOptions, WriteResult};
+use codem_core::types::{GrepCodebaseResult, GrepFileMatch, GrepOptions, WriteResult};
 use std::path::Path;
 
 pub struct Client {
     pub(crate) sessions: SessionManager,
+    pub(crate) jobs: JobManager,
+    pub(crate) history: CommandHistory,
+    pub(crate) approvals: ApprovalQueue,
+    pub(crate) test_cache: TestCache,
+    pub(crate) replace_previews: ReplacePreviews,
 }
 
 impl Client {
     pub async fn new(config: ClientConfig) -> Self {
         Self {
+            history: CommandHistory::new(&config.session_file),
+            approvals: ApprovalQueue::new(&config.session_file),
             sessions: SessionManager::new(config).await,
+            jobs: JobManager::new(),
+            test_cache: TestCache::new(),
+            replace_previews: ReplacePreviews::default(),
         }
     }
 
@@ -37,8 +56,7 @@ impl Client {
         session_id: &str,
         path: impl AsRef<Path>,
         pattern: &str,
-        case_sensitive: bool,
-        context_lines: usize,
+        options: &GrepOptions,
     ) -> Result<Vec<GrepFileMatch>, ClientError> {
         let path = path.as_ref();
         
@@ -51,14 +69,15 @@ impl Client {
         // Validate the path
         self.sessions.check_path(session_id, &absolute_path).await?;
         
-        let pattern = regex::Regex::new(pattern).map_err(|_| ClientError::InvalidPath {
+        let pattern = codem_core::grep::build_pattern(pattern, options).map_err(|_| ClientError::InvalidPath {
             path: absolute_path.clone(),
         })?;
         
+        // A single file is searched whatever its name or path
         let opts = GrepOptions {
-            case_sensitive,
-            context_lines,
-            ..Default::default()
+            file_pattern: None,
+            path_filter: None,
+            ..options.clone()
         };
 
         let result = codem_core::grep::grep_file(&absolute_path, &pattern, &opts).await?;
@@ -76,11 +95,9 @@ impl Client {
         &self,
         session_id: &str,
         path: Option<&Path>,
-        file_pattern: Option<&str>,
         pattern: &str,
-        case_sensitive: bool,
-        context_lines: usize,
-    ) -> Result<Vec<GrepFileMatch>, ClientError> {
+        options: &GrepOptions,
+    ) -> Result<GrepCodebaseResult, ClientError> {
         // Get session to access project
         let session = self.sessions.get_session(session_id).await?;
         
@@ -94,26 +111,31 @@ impl Client {
         // Validate the path
         self.sessions.check_path(session_id, &absolute_path).await?;
 
-        let pattern = regex::Regex::new(pattern).map_err(|_| ClientError::InvalidPath {
+        let pattern = codem_core::grep::build_pattern(pattern, options).map_err(|_| ClientError::InvalidPath {
             path: absolute_path.clone(),
         })?;
         
-        let opts = GrepOptions {
-            file_pattern: file_pattern.map(ToString::to_string),
-            case_sensitive,
-            context_lines
+        let options = GrepOptions {
+            path_filter: grep::grep_path_filter(options.path_filter.as_ref(), &session.project.base_path)?,
+            ..options.clone()
         };
         
-        let matches = codem_core::grep::grep_codebase(&absolute_path, &pattern, &opts).await?;
+        let mut results = codem_core::grep::grep_codebase(&absolute_path, &pattern, &options).await?;
         
         // Convert absolute paths to relative paths
-        let relative_matches = matches.into_iter().map(|mut match_result| {
-            match_result.path = match_result.path.strip_prefix(&session.project.base_path)
-                .unwrap_or(&match_result.path)
-                .to_path_buf();
-            match_result
-        }).collect();
+        let relative = |path: &Path| path.strip_prefix(&session.project.base_path)
+            .unwrap_or(path)
+            .to_path_buf();
+        for match_result in &mut results.files {
+            match_result.path = relative(&match_result.path);
+        }
+        for error in &mut results.errors {
+            error.path = relative(&error.path);
+        }
+        // Files are searched concurrently, so they finish in any order
+        results.files.sort_by(|a, b| a.path.cmp(&b.path));
+        results.errors.sort_by(|a, b| a.path.cmp(&b.path));
         
-        Ok(relative_matches)
+        Ok(results)
     }
 }
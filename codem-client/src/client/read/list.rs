@@ -1,13 +1,13 @@
 use std::path::Path;
 use codem_core::{types::TreeEntry, error::DirectoryError};
-use crate::{Client, error::ClientError};
+use crate::{Client, client::grep::project_path_filter, error::ClientError};
 
 impl Client {
     pub async fn list_directory(
         &self,
         session_id: &str,
         path: Option<&Path>,
-        options: codem_core::types::ListOptions,
+        mut options: codem_core::types::ListOptions,
     ) -> Result<TreeEntry, ClientError> {
         let path = path.as_ref();
 
@@ -24,6 +24,8 @@ impl Client {
         // Validate the path
         self.sessions.check_path(session_id, &absolute_path).await?;
 
+        options.path_filter = project_path_filter(options.path_filter.as_ref(), &session.project.base_path)?;
+
         // List directory using codem_core
         let tree = codem_core::directory::list_directory(&absolute_path, &absolute_path, &options)
             .await
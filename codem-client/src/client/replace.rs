@@ -0,0 +1,276 @@
+use std::{collections::HashMap, ops::Range, path::{Path, PathBuf}, time::SystemTime};
+use codem_core::{
+    fs_write::write_file,
+    types::{GrepFileError, GrepOptions, WriteOperation},
+};
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
+use tokio::fs;
+use crate::{
+    error::{grep_error::Pattern, ClientError, GrepError},
+    types::{FileReplacement, ReplaceHunk, ReplaceResult},
+};
+
+/// Timestamps of the files each session's replacements were previewed
+/// against. Kept apart from the session's read timestamps, since a preview
+/// doesn't show the agent the whole file and shouldn't let it write one.
+#[derive(Default)]
+pub(crate) struct ReplacePreviews {
+    files: Mutex<HashMap<(String, PathBuf), SystemTime>>,
+}
+
+impl ReplacePreviews {
+    fn record(&self, session_id: &str, path: &Path, modified: SystemTime) {
+        self.files.lock().insert((session_id.to_string(), path.to_path_buf()), modified);
+    }
+
+    fn get(&self, session_id: &str, path: &Path) -> Option<SystemTime> {
+        self.files.lock().get(&(session_id.to_string(), path.to_path_buf())).copied()
+    }
+
+    fn remove(&self, session_id: &str, path: &Path) {
+        self.files.lock().remove(&(session_id.to_string(), path.to_path_buf()));
+    }
+}
+
+/// A file's new content, kept until the replacement is applied
+struct FileEdit {
+    path: PathBuf,
+    content: String,
+    modified: SystemTime,
+}
+
+impl crate::Client {
+    /// Replace every match of `pattern` in the codebase with `replacement`,
+    /// which can refer to capture groups as `$1` or `${name}` unless the
+    /// pattern is a fixed string. Files are found as `grep_codebase` finds
+    /// them, so the same options and path filters apply.
+    ///
+    /// Without `apply` the edits are only previewed, and each file's
+    /// timestamp is recorded for applying them later; it doesn't count as
+    /// reading the file for other writes. Applying writes the
+    /// edits, but fails with `ReplaceNotPreviewed`, writing nothing, if any
+    /// file to be edited has changed since it was previewed or read. A file
+    /// that can't be written is added to the errors and the rest are still
+    /// written; `written` lists the ones that were.
+    pub async fn replace_codebase(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+        pattern: &str,
+        replacement: &str,
+        options: &GrepOptions,
+        apply: bool,
+    ) -> Result<ReplaceResult, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+
+        // Binary files can't be edited, and grep's context isn't needed
+        let options = GrepOptions {
+            context_lines: 0,
+            search_binary: false,
+            ..options.clone()
+        };
+        let regex = replace_pattern(pattern, &options)?;
+        let found = self.grep_codebase(session_id, path, pattern, &options).await?;
+
+        let mut result = ReplaceResult {
+            errors: found.errors,
+            ..Default::default()
+        };
+        let mut edits = Vec::new();
+
+        for file in found.files {
+            if let Some(encoding) = file.encoding {
+                result.errors.push(GrepFileError {
+                    path: file.path,
+                    message: format!("encoded as {}; only UTF-8 files are replaced", encoding),
+                });
+                continue;
+            }
+
+            let absolute_path = session.project.base_path.join(&file.path);
+            self.sessions.check_path(session_id, &absolute_path).await?;
+
+            // The timestamp is taken first, so a change made while reading
+            // makes the file look stale rather than up to date
+            let read = async {
+                let modified = fs::metadata(&absolute_path).await?.modified()?;
+                Ok::<_, std::io::Error>((modified, fs::read_to_string(&absolute_path).await?))
+            };
+            let (modified, content) = match read.await {
+                Ok(read) => read,
+                Err(err) => {
+                    result.errors.push(GrepFileError { path: file.path, message: err.to_string() });
+                    continue;
+                }
+            };
+
+            let replacements = find_replacements(&content, &regex, replacement, &options);
+            if replacements.is_empty() {
+                continue;
+            }
+            let (new_content, hunks) = apply_replacements(&content, &replacements);
+            result.files.push(FileReplacement {
+                path: file.path,
+                replacements: replacements.len(),
+                hunks,
+            });
+            edits.push(FileEdit { path: absolute_path, content: new_content, modified });
+        }
+
+        if !apply {
+            for edit in &edits {
+                self.replace_previews.record(session_id, &edit.path, edit.modified);
+            }
+            return Ok(result);
+        }
+
+        // Check every file before writing any, so a stale file doesn't leave
+        // the replacement half done
+        let mut stale = Vec::new();
+        for (edit, file) in edits.iter().zip(&result.files) {
+            let previewed = self.replace_previews.get(session_id, &edit.path);
+            let read = session.recorded_timestamp(&edit.path).await;
+            if previewed != Some(edit.modified) && read != Some(edit.modified) {
+                stale.push(file.path.clone());
+            }
+        }
+        if !stale.is_empty() {
+            return Err(ClientError::ReplaceNotPreviewed { paths: stale });
+        }
+
+        for (edit, file) in edits.into_iter().zip(&result.files) {
+            self.replace_previews.remove(session_id, &edit.path);
+            let written = match write_file(&edit.path, WriteOperation::Full(edit.content), Some(edit.modified)).await {
+                Ok(written) => written,
+                Err(err) => {
+                    result.errors.push(GrepFileError { path: file.path.clone(), message: format!("not written: {}", err) });
+                    continue;
+                }
+            };
+            session.update_timestamp(&edit.path, written.modified).await?;
+            session.record_write(&edit.path).await?;
+            result.written.push(file.path.clone());
+        }
+        result.applied = true;
+
+        Ok(result)
+    }
+}
+
+/// Compile the pattern the way grep matches it, so the replacements land on
+/// what the search found. Like grep, `$` matches before `\r\n` as well as `\n`.
+fn replace_pattern(pattern: &str, options: &GrepOptions) -> Result<Regex, ClientError> {
+    let pattern = if options.fixed_string {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    // grep's word mode only needs a non-word character (or the edge of the
+    // line) next to the match, so `-foo` and `foo(` match as words too,
+    // which `\b` would miss
+    let pattern = if options.whole_word {
+        format!(r"\b{{start-half}}(?:{})\b{{end-half}}", pattern)
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .multi_line(true)
+        .crlf(true)
+        .build()
+        .map_err(|e| GrepError::InvalidPattern(Pattern(e.to_string())).into())
+}
+
+/// Byte range of each match that the replacement changes, with its new
+/// text. Unless the search is multiline, each line is matched on its own
+/// without its line ending, as grep does.
+fn find_replacements(
+    content: &str,
+    regex: &Regex,
+    replacement: &str,
+    options: &GrepOptions,
+) -> Vec<(Range<usize>, String)> {
+    let mut sections = Vec::new();
+    if options.multiline {
+        sections.push((0, content));
+    } else {
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            sections.push((offset, text.strip_suffix('\r').unwrap_or(text)));
+            offset += line.len();
+        }
+    }
+
+    let mut replacements = Vec::new();
+    for (offset, text) in sections {
+        for captures in regex.captures_iter(text) {
+            let hit = captures.get(0).expect("group 0 is the whole match");
+            let mut new_text = String::new();
+            if options.fixed_string {
+                new_text.push_str(replacement);
+            } else {
+                captures.expand(replacement, &mut new_text);
+            }
+            if new_text != hit.as_str() {
+                replacements.push((offset + hit.start()..offset + hit.end(), new_text));
+            }
+        }
+    }
+    replacements
+}
+
+/// The content with the replacements made, and the lines they change.
+/// Replacements touching the same lines share a hunk.
+fn apply_replacements(content: &str, replacements: &[(Range<usize>, String)]) -> (String, Vec<ReplaceHunk>) {
+    let line_start = |pos: usize| content[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = |pos: usize| content[pos..].find('\n').map_or(content.len(), |i| pos + i);
+
+    let mut new_content = String::with_capacity(content.len());
+    let mut hunks = Vec::new();
+    // How far the content has been copied and its lines counted
+    let (mut copied, mut line) = (0, 1);
+    let mut line_delta: isize = 0;
+
+    let mut next = 0;
+    while next < replacements.len() {
+        let start = line_start(replacements[next].0.start);
+        let mut end = line_end(replacements[next].0.end);
+        let mut last = next + 1;
+        while last < replacements.len() && replacements[last].0.start <= end {
+            end = end.max(line_end(replacements[last].0.end));
+            last += 1;
+        }
+
+        let mut new_text = String::new();
+        let mut pos = start;
+        for (range, text) in &replacements[next..last] {
+            new_text.push_str(&content[pos..range.start]);
+            new_text.push_str(text);
+            pos = range.end;
+        }
+        new_text.push_str(&content[pos..end]);
+
+        line += content[copied..start].matches('\n').count();
+        let old_lines: Vec<String> = content[start..end].split('\n').map(String::from).collect();
+        let new_lines: Vec<String> = new_text.split('\n').map(String::from).collect();
+        hunks.push(ReplaceHunk {
+            old_start: line,
+            new_start: (line as isize + line_delta) as usize,
+            old_lines,
+            new_lines,
+        });
+        let hunk = hunks.last().expect("just pushed");
+        line_delta += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+        line += hunk.old_lines.len() - 1;
+
+        new_content.push_str(&content[copied..start]);
+        new_content.push_str(&new_text);
+        copied = end;
+        next = last;
+    }
+    new_content.push_str(&content[copied..]);
+
+    (new_content, hunks)
+}
@@ -1,35 +1,102 @@
-use codem_core::command::run_command;
+use std::time::Instant;
+use codem_core::{command::run_command_with_options, types::OutputSender, CommandError};
 use crate::{
-    error::{ClientError, OperationError},
-    project::Project,
-    types::file_ops::WriteResultWithChecks,
+    client::command::combine_output,
+    error::ClientError,
+    history::RunTimer,
+    session::manager::session::Session,
+    types::{CheckReport, CheckResult, CheckStatus, RunKind},
 };
 
-pub(super) async fn run_checks(
-    result: &mut WriteResultWithChecks,
-    project: &Project,
-    _path: &std::path::Path,
-) -> Result<(), ClientError> {
-    if let Some(check_cmd) = &project.check_command {
-        let output = run_command(check_cmd, None, None)
-            .await
-            .map_err(|e| OperationError::CommandError(e))?;
-        result.check_results.push(format!("Check results:\n{}", output.stdout));
-    }
+impl crate::Client {
+    /// Run the check stages configured for the session's project.
+    ///
+    /// `stop_on_failure` overrides the project's `stop_checks_on_failure`.
+    pub async fn run_checks(
+        &self,
+        session_id: &str,
+        stages: Option<&[String]>,
+        stop_on_failure: Option<bool>,
+        output: Option<OutputSender>,
+    ) -> Result<CheckReport, ClientError> {
+        let session = self.sessions.get_session(session_id).await?;
+        let project = &session.project;
 
-    if let Some(lint_cmd) = &project.lint_command {
-        let output = run_command(lint_cmd, None, None)
-            .await
-            .map_err(|e| OperationError::CommandError(e))?;
-        result.check_results.push(format!("Lint results:\n{}", output.stdout));
-    }
+        if project.checks.is_empty() {
+            return Err(ClientError::ChecksNotConfigured);
+        }
+        if let Some(name) = stages.into_iter().flatten().find(|name| !project.checks.iter().any(|s| &s.name == *name)) {
+            return Err(ClientError::CheckStageNotFound { name: name.clone() });
+        }
 
-    if let Some(test_cmd) = &project.test_command {
-        let output = run_command(test_cmd, None, None)
-            .await
-            .map_err(|e| OperationError::CommandError(e))?;
-        result.check_results.push(format!("Test results:\n{}", output.stdout));
+        let stop_on_failure = stop_on_failure.unwrap_or(project.stop_checks_on_failure);
+        self.run_check_stages(&session, stages, stop_on_failure, output).await
     }
 
-    Ok(())
-}
\ No newline at end of file
+    /// Run the project's check stages in order, recording each in the
+    /// session's history.
+    ///
+    /// `stages` limits the run to the stages with those names. With
+    /// `stop_on_failure`, the stages after the first failing one are skipped.
+    pub(crate) async fn run_check_stages(
+        &self,
+        session: &Session,
+        stages: Option<&[String]>,
+        stop_on_failure: bool,
+        output: Option<OutputSender>,
+    ) -> Result<CheckReport, ClientError> {
+        let project = &session.project;
+        let mut report = CheckReport::default();
+        let mut failed = false;
+
+        let selected = project.checks.iter()
+            .filter(|stage| stages.is_none_or(|names| names.contains(&stage.name)));
+        for stage in selected {
+            if failed && stop_on_failure {
+                report.results.push(CheckResult {
+                    name: stage.name.clone(),
+                    command: stage.command.clone(),
+                    status: CheckStatus::Skipped,
+                    output: String::new(),
+                    duration: Default::default(),
+                });
+                continue;
+            }
+
+            let mut options = project.command_options(&project.base_path)?;
+            options.output = output.clone();
+            options.timeout_ms = stage.timeout_ms;
+
+            let timer = RunTimer::start(RunKind::Check, &stage.command, &project.base_path);
+            let started = Instant::now();
+            let result = run_command_with_options(&stage.command, &options).await.map_err(ClientError::from);
+            self.record_run(session, timer.finish_command(result.as_ref())).await;
+
+            let (status, output) = match result {
+                Ok(output) if output.exit_code == 0 => (CheckStatus::Passed, combine_output(&output)),
+                Ok(output) => (CheckStatus::Failed { exit_code: output.exit_code }, combine_output(&output)),
+                // Keep what the command printed before it was stopped
+                Err(ClientError::CommandError(CommandError::Timeout { timeout_ms, output, .. })) => (
+                    CheckStatus::Error { error: format!("timed out after {}ms", timeout_ms) },
+                    combine_output(&output),
+                ),
+                Err(ClientError::CommandError(CommandError::LimitExceeded { limit, output, .. })) => (
+                    CheckStatus::Error { error: format!("killed after exceeding its {}", limit) },
+                    combine_output(&output),
+                ),
+                Err(e) => (CheckStatus::Error { error: e.to_string() }, String::new()),
+            };
+
+            failed |= status != CheckStatus::Passed;
+            report.results.push(CheckResult {
+                name: stage.name.clone(),
+                command: stage.command.clone(),
+                status,
+                output,
+                duration: started.elapsed(),
+            });
+        }
+
+        Ok(report)
+    }
+}
@@ -5,6 +5,7 @@ use codem_core::{
 }; 
 use crate::{Client, error::ClientError};
 
+pub(crate) mod checks;
 pub(crate) mod operations;
 
 impl Client {
@@ -43,7 +44,7 @@ impl Client {
 
         // Get current timestamp if available
         let current_timestamp = metadata.modified.ok_or_else(|| ClientError::IoError(
-            std::io::Error::new(std::io::ErrorKind::Other, "Could not get file timestamp")
+            std::io::Error::other("Could not get file timestamp")
         ))?;
 
         // Verify timestamps match
@@ -112,7 +113,7 @@ impl Client {
 
         // Get current timestamp if available
         let current_timestamp = metadata.modified.ok_or_else(|| ClientError::IoError(
-            std::io::Error::new(std::io::ErrorKind::Other, "Could not get file timestamp")
+            std::io::Error::other("Could not get file timestamp")
         ))?;
 
         // Verify timestamps match
@@ -190,7 +191,7 @@ impl Client {
 
         // Get current timestamp if available
         let current_timestamp = metadata.modified.ok_or_else(|| ClientError::IoError(
-            std::io::Error::new(std::io::ErrorKind::Other, "Could not get file timestamp")
+            std::io::Error::other("Could not get file timestamp")
         ))?;
 
         // Verify timestamps match
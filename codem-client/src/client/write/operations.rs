@@ -1,8 +1,7 @@
-use crate::error::ClientError;
+use crate::{error::ClientError, session::manager::session::Session};
 use codem_core::{
     fs_write::{write_file, write_new_file},
     types::{WriteOperation, WriteResult, WriteResultDetails},
-    command::run_command
 };
 use std::path::Path;
 
@@ -26,24 +25,17 @@ pub(crate) async fn handle_operation(
     session.get_timestamp(&absolute_path).await?;
 
     // Perform the write operation
-    let mut result = match write_file(&absolute_path, operation, None).await {
+    let result = match write_file(&absolute_path, operation, None).await {
         Ok(result) => result,
         Err(e) => return Err(ClientError::WriteError(e))
     };
 
     // Update session's timestamp tracking with the new timestamp
     session.update_timestamp(&absolute_path, result.modified).await?;
+    session.record_write(&absolute_path).await?;
 
-    // Only run test command if write succeeded
-    if run_test {
-        let test_output = run_test_command(&session).await?;
-        result.details = WriteResultDetails::WithTestOutput {
-            output: test_output,
-            details: Box::new(result.details)
-        };
-    }
-
-    Ok(result)
+    // Only run checks and test command if write succeeded
+    run_after_write(client, &session, result, run_test).await
 }
 
 pub(crate) async fn handle_new_file(
@@ -63,7 +55,7 @@ pub(crate) async fn handle_new_file(
     client.sessions.check_path(session_id, &absolute_path).await?;
 
     // Create the new file
-    let mut result = match write_new_file(&absolute_path, content).await {
+    let result = match write_new_file(&absolute_path, content).await {
         Ok(result) => result,
         Err(e) => return Err(match e {
             codem_core::WriteError::FileExists { content } => ClientError::WriteError(
@@ -75,10 +67,34 @@ pub(crate) async fn handle_new_file(
 
     // Update session's timestamp tracking with the new file's timestamp
     session.update_timestamp(&absolute_path, result.modified).await?;
+    session.record_write(&absolute_path).await?;
+
+    // Only run checks and test command if write succeeded
+    run_after_write(client, &session, result, run_test).await
+}
+
+/// Run the project's checks (if configured to run after writes) and the
+/// test command (if requested), adding their output to the write result.
+/// Both are recorded in the session's history like any other run.
+async fn run_after_write(
+    client: &crate::Client,
+    session: &Session,
+    mut result: WriteResult,
+    run_test: bool,
+) -> Result<WriteResult, ClientError> {
+    let project = &session.project;
+
+    if project.checks_after_write && !project.checks.is_empty() {
+        let report = client.run_check_stages(session, None, project.stop_checks_on_failure, None).await?;
+        result.details = WriteResultDetails::WithCheckOutput {
+            output: report.to_string(),
+            passed: report.passed(),
+            details: Box::new(result.details)
+        };
+    }
 
-    // Only run test command if write succeeded
     if run_test {
-        let test_output = run_test_command(&session).await?;
+        let test_output = run_test_command(client, session).await?;
         result.details = WriteResultDetails::WithTestOutput {
             output: test_output,
             details: Box::new(result.details)
@@ -88,29 +104,10 @@ pub(crate) async fn handle_new_file(
     Ok(result)
 }
 
-async fn run_test_command(session: &crate::session::manager::session::Session) -> Result<String, ClientError> {
-    let test_cmd = session.project.test_command.as_ref().ok_or(ClientError::TestCommandNotConfigured)?;
-
-    let output = run_command(test_cmd, Some(&session.project.base_path), None).await
-        .map_err(ClientError::CommandError)?;
-
-    if output.exit_code != 0 {
-        return Err(ClientError::TestCommandFailed {
-            stdout: output.stdout, stderr: output.stderr, exit_code: output.exit_code
-        });
-    }
-
-    // Combine stdout and stderr like in command.rs
-    let mut combined = String::new();
-    if !output.stdout.is_empty() {
-        combined.push_str(&output.stdout);
-    }
-    if !output.stderr.is_empty() {
-        if !combined.is_empty() {
-            combined.push('\n');
-        }
-        combined.push_str(&output.stderr);
-    }
-
-    Ok(combined)
-}
\ No newline at end of file
+async fn run_test_command(client: &crate::Client, session: &Session) -> Result<String, ClientError> {
+    let test_command = session.project.test_command
+        .as_ref()
+        .ok_or(ClientError::TestCommandNotConfigured)?;
+    let run = client.run_tests(session, test_command, false, None).await?;
+    Ok(run.summary())
+}
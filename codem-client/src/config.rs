@@ -4,7 +4,8 @@ use std::sync::Arc;
 use regex::Regex;
 
 use crate::error::ConfigError;
-use crate::project::Project;
+use crate::project::{PatternMode, Project};
+use crate::safety::{self, UnsafeReason};
 
 /// Configuration for the Codem client
 #[derive(Debug, Clone)]
@@ -28,7 +29,8 @@ impl ClientConfig {
     /// # Errors
     /// - Returns `ConfigError::InvalidSessionFile` if the session file path is not valid
     /// - Returns `ConfigError::InvalidProject` if a project is invalid
-    /// - Returns `ConfigError::InvalidPattern` if a pattern is empty or invalid regex
+    /// - Returns `ConfigError::InvalidPattern` if a pattern is empty or invalid regex,
+    ///   or an impact mapping path is not a valid glob
     pub fn new(
         projects: Vec<Project>,
         session_file: PathBuf, 
@@ -36,39 +38,21 @@ impl ClientConfig {
         risky_patterns: Vec<String>
     ) -> Result<Self, ConfigError> {
         // Validate session file path
-        if !session_file.parent().map_or(false, |p| p.exists()) {
+        if !session_file.parent().is_some_and(|p| p.exists()) {
             return Err(ConfigError::InvalidSessionFile { 
                 path: session_file
             });
         }
 
         // Validate patterns
-        for pattern in &safe_patterns {
-            if pattern.is_empty() {
-                return Err(ConfigError::InvalidPattern {
-                    pattern: pattern.clone()
-                });
-            }
-            // Validate as regex
-            if Regex::new(pattern).is_err() {
-                return Err(ConfigError::InvalidPattern {
-                    pattern: pattern.clone()
-                });
-            }
-        }
+        validate_patterns(&safe_patterns)?;
+        validate_patterns(&risky_patterns)?;
 
-        for pattern in &risky_patterns {
-            if pattern.is_empty() {
-                return Err(ConfigError::InvalidPattern {
-                    pattern: pattern.clone() 
-                });
-            }
-            // Validate as regex
-            if Regex::new(pattern).is_err() {
-                return Err(ConfigError::InvalidPattern {
-                    pattern: pattern.clone()
-                });
-            }
+        for project in &projects {
+            validate_patterns(&project.safe_patterns)?;
+            validate_patterns(&project.risky_patterns)?;
+            validate_patterns(&project.secret_patterns)?;
+            validate_globs(project.impact.mappings.iter().flat_map(|m| &m.paths))?;
         }
 
         let projects = projects.into_iter()
@@ -85,30 +69,60 @@ impl ClientConfig {
 
     /// Check if a command is safe to execute
     ///
-    /// A command is considered safe if:
-    /// 1. It matches at least one safe pattern AND
-    /// 2. It doesn't match any risky patterns
+    /// A command is considered safe if every simple command it runs:
+    /// 1. Matches at least one safe pattern AND
+    /// 2. Doesn't match any risky patterns AND
+    /// 3. Doesn't redirect output into a file
     /// 
     /// Otherwise, the command is risky.
     pub fn is_command_safe(&self, command: &str) -> bool {
-        // First check risky patterns - if any match, command is not safe
-        for pattern in &self.risky_patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(command) {
-                    return false;
-                }
+        self.check_command(command).is_ok()
+    }
+
+    /// Like `is_command_safe`, but explains which part of the command was rejected
+    pub fn check_command(&self, command: &str) -> Result<(), UnsafeReason> {
+        safety::check_command(command, &self.safe_patterns, &self.risky_patterns)
+    }
+
+    /// Check a command against the patterns in effect for a project
+    ///
+    /// Depending on the project's `pattern_mode`, its patterns either extend
+    /// or replace the global ones.
+    pub fn check_project_command(&self, project: &Project, command: &str) -> Result<(), UnsafeReason> {
+        match project.pattern_mode {
+            PatternMode::Override => {
+                safety::check_command(command, &project.safe_patterns, &project.risky_patterns)
+            }
+            PatternMode::Extend => {
+                let safe_patterns = [self.safe_patterns.as_slice(), &project.safe_patterns].concat();
+                let risky_patterns = [self.risky_patterns.as_slice(), &project.risky_patterns].concat();
+                safety::check_command(command, &safe_patterns, &risky_patterns)
             }
         }
+    }
+}
 
-        // Then check safe patterns - must match at least one
-        self.safe_patterns.iter().any(|p| {
-            if let Ok(re) = Regex::new(p) {
-                re.is_match(command)
-            } else {
-                false
-            }
-        })
+fn validate_patterns(patterns: &[String]) -> Result<(), ConfigError> {
+    for pattern in patterns {
+        // Patterns must be non-empty, valid regexes
+        if pattern.is_empty() || Regex::new(pattern).is_err() {
+            return Err(ConfigError::InvalidPattern {
+                pattern: pattern.clone()
+            });
+        }
+    }
+    Ok(())
+}
+
+fn validate_globs<'a>(globs: impl IntoIterator<Item = &'a String>) -> Result<(), ConfigError> {
+    for glob in globs {
+        if glob.is_empty() || glob::Pattern::new(glob).is_err() {
+            return Err(ConfigError::InvalidPattern {
+                pattern: glob.clone()
+            });
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -116,6 +130,7 @@ mod tests {
     use super::*;
     use std::fs;
     use rstest::rstest;
+    use crate::project::ImpactMapping;
 
     fn setup_test_dir() -> PathBuf {
         let temp_dir = std::env::temp_dir().join("codem_test");
@@ -133,6 +148,14 @@ mod tests {
     #[case("cargo test", vec![], vec!["^cargo.*".into()], false)]
     #[case("cargo test", vec!["^cargo.*".into()], vec!["^cargo test$".into()], false)]
     #[case("cp some/file.txt", vec!["^cp .*".into()], vec!["^.*rm.*$".into()], true)]
+    #[case("cargo test && rm -rf target", vec!["^cargo.*".into()], vec![], false)]
+    #[case("cargo test && cargo build", vec!["^cargo.*".into()], vec![], true)]
+    #[case("cargo test | grep ok", vec!["^cargo.*".into()], vec![], false)]
+    #[case("cargo test | grep ok", vec!["^cargo.*".into(), "^grep .*".into()], vec![], true)]
+    #[case("cargo test $(rm -rf /)", vec!["^cargo.*".into()], vec![], false)]
+    #[case("cargo test > src/main.rs", vec!["^cargo.*".into()], vec![], false)]
+    #[case("cargo test 2>&1", vec!["^cargo.*".into()], vec![], true)]
+    #[case("cargo test 'unterminated", vec!["^cargo.*".into()], vec![], false)]
     fn test_is_command_safe(
         #[case] command: &str,
         #[case] safe_patterns: Vec<String>,
@@ -155,6 +178,65 @@ mod tests {
         cleanup_test_dir(temp_dir);
     }
 
+    #[test]
+    fn test_check_command_explains_rejection() {
+        let temp_dir = setup_test_dir();
+
+        let config = ClientConfig::new(
+            vec![Project::new(temp_dir.clone())],
+            temp_dir.join("session").join("session.toml"),
+            vec!["^cargo .*".to_string()],
+            vec!["^rm .*".to_string()]
+        ).unwrap();
+
+        assert_eq!(config.check_command("cargo test && rm -rf target"), Err(UnsafeReason::RiskyPattern {
+            segment: "rm -rf target".to_string(),
+            pattern: "^rm .*".to_string(),
+        }));
+        assert_eq!(config.check_command("cargo test; ls"), Err(UnsafeReason::NoSafePattern {
+            segment: "ls".to_string(),
+        }));
+        assert_eq!(config.check_command("cargo test >> log.txt"), Err(UnsafeReason::FileRedirection {
+            segment: "cargo test".to_string(),
+            target: "log.txt".to_string(),
+        }));
+        assert_eq!(config.check_command("  "), Err(UnsafeReason::Empty));
+        assert!(config.check_command("cargo test && cargo build").is_ok());
+
+        cleanup_test_dir(temp_dir);
+    }
+
+    #[rstest]
+    #[case("cargo test", PatternMode::Extend, true)]
+    #[case("pytest -x", PatternMode::Extend, true)]
+    #[case("cargo publish", PatternMode::Extend, false)]
+    #[case("cargo test", PatternMode::Override, false)]
+    #[case("pytest -x", PatternMode::Override, true)]
+    #[case("rm -rf build", PatternMode::Override, false)]
+    fn test_project_patterns(
+        #[case] command: &str,
+        #[case] pattern_mode: PatternMode,
+        #[case] expected: bool
+    ) {
+        let temp_dir = setup_test_dir();
+
+        let mut project = Project::new(temp_dir.clone());
+        project.safe_patterns = vec!["^pytest.*".to_string()];
+        project.risky_patterns = vec!["^cargo publish.*".to_string()];
+        project.pattern_mode = pattern_mode;
+
+        let config = ClientConfig::new(
+            vec![project.clone()],
+            temp_dir.join("session").join("session.toml"),
+            vec!["^cargo.*".to_string()],
+            vec!["^rm .*".to_string()]
+        ).unwrap();
+
+        assert_eq!(config.check_project_command(&project, command).is_ok(), expected);
+
+        cleanup_test_dir(temp_dir);
+    }
+
     #[test] 
     fn test_invalid_pattern() {
         let temp_dir = setup_test_dir();
@@ -176,7 +258,32 @@ mod tests {
             vec![]
         );
         assert!(matches!(result, Err(ConfigError::InvalidPattern { .. })));
-        
+
+        // Test invalid project pattern
+        let mut project = Project::new(temp_dir.clone());
+        project.risky_patterns = vec!["[".to_string()];
+        let result = ClientConfig::new(
+            vec![project],
+            temp_dir.join("session").join("session.toml"),
+            vec![],
+            vec![]
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidPattern { .. })));
+
+        // Test invalid impact mapping glob
+        let mut project = Project::new(temp_dir.clone());
+        project.impact.mappings = vec![ImpactMapping {
+            paths: vec!["src/[".to_string()],
+            targets: vec!["unit".to_string()],
+        }];
+        let result = ClientConfig::new(
+            vec![project],
+            temp_dir.join("session").join("session.toml"),
+            vec![],
+            vec![]
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidPattern { .. })));
+
         cleanup_test_dir(temp_dir);
     }
 
@@ -32,6 +32,12 @@ error_set! {
         ProcessError {
             path: String,
             source: std::io::Error
-        }
+        },
+        #[display("Invalid grep cursor {cursor}; use the next_cursor from a previous page")]
+        InvalidCursor { cursor: String },
+        #[display("Grep cursor {cursor} is from a different search; repeat it with the same path, pattern and options, or drop the cursor to start over")]
+        CursorMismatch { cursor: String },
+        #[display("{name} must be at least 1")]
+        InvalidLimit { name: String }
     };
 }
\ No newline at end of file
@@ -8,6 +8,7 @@ use error_set::error_set;
 use codem_core::error::{WriteError, CommandError, DirectoryError};
 
 use crate::session::manager::path::PathValidator;
+use crate::types::{ApprovalStatus, TestReport};
 
 pub trait ToRelativePath {
     fn to_relative_display(&self, validator: &PathValidator) -> String;
@@ -26,6 +27,7 @@ impl From<DirectoryError> for ClientError {
             DirectoryError::RegexError(_e) => ClientError::InvalidPath { 
                 path: PathBuf::from("Invalid regex pattern in file filter") 
             },
+            DirectoryError::GlobError(e) => ClientError::InvalidPathGlob(e),
         }
     }
 }
@@ -44,6 +46,14 @@ error_set! {
         WriteError(WriteError),
         #[display("Command error: {0}")]
         CommandError(CommandError),
+        #[display("Invalid path glob: {0}")]
+        InvalidPathGlob(ignore::Error),
+        #[display("Command error: {error}")]
+        CommandFailedWithChanges {
+            error: CommandError,
+            changed_files: Vec<PathBuf>,
+            timestamps_refreshed: bool,
+        },
         #[display("Session not found: {id}")]
         SessionNotFound { id: String },
         #[display("Attempted to write to a file that was not previously read in this session. The file contents have now been read and are below, so you can try writing again.")]
@@ -103,11 +113,17 @@ error_set! {
         GrepError(GrepError),
         #[display("Test command not configured")]
         TestCommandNotConfigured,
+        #[display("No checks configured for this project")]
+        ChecksNotConfigured,
+        #[display("Check stage not found: {name}")]
+        CheckStageNotFound { name: String },
         #[display("Test command failed (exit code {exit_code}):\nstdout:\n{stdout}\nstderr:\n{stderr}")]
         TestCommandFailed {
             stdout: String,
             stderr: String,
             exit_code: i32,
+            report: Option<Box<TestReport>>,
+            cached_at: Option<SystemTime>,
         },
         #[display("Toml deserialize error: {0}")]
         TomlDeserializeError(toml::de::Error),
@@ -115,7 +131,25 @@ error_set! {
         TomlSerializeError(toml::ser::Error),
         #[display("Command not recognized: {command}")]
         InvalidCommand { command: String },
-        #[display("Command requires timeout: {command}")]
-        UnsafeCommand { command: String },
+        #[display("Command is not marked as safe: {command} ({reason})")]
+        UnsafeCommand { command: String, reason: String },
+        #[display("Job not found: {id}")]
+        JobNotFound { id: String },
+        #[display("Command run not found in session history: {id}")]
+        CommandRunNotFound { id: u64 },
+        #[display("Command is waiting for approval (request {id})")]
+        ApprovalPending { id: u64 },
+        #[display("Command was denied (request {id}){}", reason.as_ref().map(|r| format!(": {}", r)).unwrap_or_default())]
+        CommandDenied { id: u64, reason: Option<String> },
+        #[display("Approval request not found: {id}")]
+        ApprovalNotFound { id: u64 },
+        #[display("Approval request {id} is {status}, not pending")]
+        ApprovalNotPending { id: u64, status: ApprovalStatus },
+        #[display("Approval request {id} doesn't match what this server submitted, so it won't be run")]
+        ApprovalInvalid { id: u64 },
+        #[display("Risky commands need approval in this project, which background jobs can't wait for. Use run_command_risky instead: {command}")]
+        JobNeedsApproval { command: String },
+        #[display("Replacement not applied: {} changed since the replacement was previewed, or weren't previewed. Preview it again before applying.", paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "))]
+        ReplaceNotPreviewed { paths: Vec<PathBuf> },
     };
 }
\ No newline at end of file
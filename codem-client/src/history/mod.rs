@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use crate::{
+    error::ClientError,
+    types::{CommandRun, RunKind},
+};
+
+/// Output kept for each run; anything beyond it is dropped before storing
+const MAX_STORED_OUTPUT: usize = 1024 * 1024;
+
+/// Commands run by each session, stored as one JSON line per run in a
+/// `history` directory next to the session file.
+pub struct CommandHistory {
+    dir: PathBuf,
+    /// Next run id per session; the lock also keeps appends in order
+    next_ids: Mutex<HashMap<String, u64>>,
+}
+
+/// A run being timed, added to the history once it finishes
+pub(crate) struct RunTimer {
+    kind: RunKind,
+    command: String,
+    cwd: PathBuf,
+    started_at: SystemTime,
+    started: Instant,
+}
+
+impl RunTimer {
+    pub(crate) fn start(kind: RunKind, command: &str, cwd: &Path) -> Self {
+        Self {
+            kind,
+            command: command.to_string(),
+            cwd: cwd.to_path_buf(),
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn finish(self, exit_code: Option<i32>, mut output: String, error: Option<String>) -> CommandRun {
+        let output_truncated = output.len() > MAX_STORED_OUTPUT;
+        if output_truncated {
+            let mut end = MAX_STORED_OUTPUT;
+            while !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.truncate(end);
+        }
+
+        CommandRun {
+            // Assigned when the run is appended
+            id: 0,
+            kind: self.kind,
+            command: self.command,
+            cwd: self.cwd,
+            started_at: self.started_at,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            exit_code,
+            error,
+            output,
+            output_truncated,
+            cached_at: None,
+        }
+    }
+}
+
+impl CommandHistory {
+    pub fn new(session_file: &Path) -> Self {
+        let dir = session_file.parent().unwrap_or(Path::new(".")).join("history");
+        Self {
+            dir,
+            next_ids: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", session_id))
+    }
+
+    /// Add a finished run to the session's history, returning its id
+    pub async fn append(&self, session_id: &str, mut run: CommandRun) -> Result<u64, ClientError> {
+        let mut next_ids = self.next_ids.lock().await;
+        let id = match next_ids.get(session_id) {
+            Some(id) => *id,
+            None => self.list(session_id).await?.last().map_or(1, |run| run.id + 1),
+        };
+        run.id = id;
+
+        let mut line = serde_json::to_string(&run).map_err(io::Error::other)?;
+        line.push('\n');
+
+        fs::create_dir_all(&self.dir).await?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(session_id))
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+
+        next_ids.insert(session_id.to_string(), id + 1);
+        Ok(id)
+    }
+
+    /// All runs in the session, oldest first
+    pub async fn list(&self, session_id: &str) -> Result<Vec<CommandRun>, ClientError> {
+        let contents = match fs::read_to_string(self.path(session_id)).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(contents.lines()
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(run) => Some(run),
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable history entry for session {}: {}", session_id, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    pub async fn get(&self, session_id: &str, id: u64) -> Result<CommandRun, ClientError> {
+        self.list(session_id).await?
+            .into_iter()
+            .find(|run| run.id == id)
+            .ok_or(ClientError::CommandRunNotFound { id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_append_and_reload() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("session.toml");
+
+        let history = CommandHistory::new(&session_file);
+        let run = RunTimer::start(RunKind::Command, "echo one", dir.path()).finish(Some(0), "one\n".to_string(), None);
+        assert_eq!(history.append("a-session", run).await.unwrap(), 1);
+        let run = RunTimer::start(RunKind::Test, "false", dir.path()).finish(Some(1), String::new(), None);
+        assert_eq!(history.append("a-session", run).await.unwrap(), 2);
+
+        // Ids continue from what's on disk
+        let history = CommandHistory::new(&session_file);
+        let run = RunTimer::start(RunKind::CommandRisky, "rm x", dir.path())
+            .finish(None, String::new(), Some("timed out".to_string()));
+        assert_eq!(history.append("a-session", run).await.unwrap(), 3);
+
+        let runs = history.list("a-session").await.unwrap();
+        let commands: Vec<_> = runs.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(commands, vec!["echo one", "false", "rm x"]);
+        assert_eq!(history.get("a-session", 1).await.unwrap().output, "one\n");
+        assert_eq!(history.get("a-session", 3).await.unwrap().error.as_deref(), Some("timed out"));
+        assert!(matches!(history.get("a-session", 4).await, Err(ClientError::CommandRunNotFound { id: 4 })));
+        assert!(history.list("other-session").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stored_output_is_capped() {
+        let output = "é".repeat(MAX_STORED_OUTPUT);
+        let run = RunTimer::start(RunKind::Command, "yes", Path::new("/")).finish(Some(0), output, None);
+        assert!(run.output_truncated);
+        assert!(run.output.len() <= MAX_STORED_OUTPUT);
+    }
+}
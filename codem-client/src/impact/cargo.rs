@@ -0,0 +1,140 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use codem_core::command::run_command_with_options;
+
+use crate::project::Project;
+
+const METADATA_COMMAND: &str = "cargo metadata --no-deps --format-version 1 --offline";
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<MetadataPackage>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+    name: String,
+    manifest_path: PathBuf,
+    dependencies: Vec<MetadataDependency>,
+}
+
+#[derive(Deserialize)]
+struct MetadataDependency {
+    /// Only set for path dependencies
+    path: Option<PathBuf>,
+}
+
+struct Package {
+    name: String,
+    dir: PathBuf,
+    /// Directories of the packages this one depends on by path
+    dependencies: Vec<PathBuf>,
+}
+
+/// The packages of a Cargo workspace and the path dependencies between them
+pub(crate) struct Workspace {
+    root: PathBuf,
+    packages: Vec<Package>,
+}
+
+impl Workspace {
+    /// Read the workspace with `cargo metadata`, run in the project's base path
+    pub(crate) async fn load(project: &Project) -> Result<Self, String> {
+        let options = project.command_options(&project.base_path).map_err(|e| e.to_string())?;
+        let output = run_command_with_options(METADATA_COMMAND, &options)
+            .await
+            .map_err(|e| e.to_string())?;
+        if output.exit_code != 0 {
+            return Err(output.stderr.trim().to_string());
+        }
+        Self::from_metadata(&output.stdout)
+    }
+
+    pub(crate) fn from_metadata(json: &str) -> Result<Self, String> {
+        let metadata: Metadata = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        let packages = metadata.packages.into_iter()
+            .map(|package| Package {
+                name: package.name,
+                dir: package.manifest_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                dependencies: package.dependencies.into_iter().filter_map(|d| d.path).collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            root: metadata.workspace_root,
+            packages,
+        })
+    }
+
+    /// The package containing `path` plus every workspace package that
+    /// depends on it, directly or not. `None` if the file isn't inside a
+    /// package or affects the whole workspace (like `Cargo.lock`).
+    pub(crate) fn affected_packages(&self, path: &Path) -> Option<BTreeSet<String>> {
+        // cargo reports canonical paths
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if path == self.root.join("Cargo.toml") || path == self.root.join("Cargo.lock") {
+            return None;
+        }
+
+        let package = self.packages.iter()
+            .filter(|p| path.starts_with(&p.dir))
+            .max_by_key(|p| p.dir.components().count())?;
+
+        let mut dependents: HashMap<&Path, Vec<&Package>> = HashMap::new();
+        for p in &self.packages {
+            for dependency in &p.dependencies {
+                dependents.entry(dependency.as_path()).or_default().push(p);
+            }
+        }
+
+        let mut affected = BTreeSet::new();
+        let mut pending = vec![package];
+        while let Some(package) = pending.pop() {
+            if affected.insert(package.name.clone()) {
+                pending.extend(dependents.get(package.dir.as_path()).into_iter().flatten());
+            }
+        }
+        Some(affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "workspace_root": "/ws",
+        "packages": [
+            {"name": "core", "manifest_path": "/ws/core/Cargo.toml", "dependencies": [{"name": "regex"}]},
+            {"name": "client", "manifest_path": "/ws/client/Cargo.toml", "dependencies": [{"name": "core", "path": "/ws/core"}]},
+            {"name": "server", "manifest_path": "/ws/server/Cargo.toml", "dependencies": [{"name": "client", "path": "/ws/client"}]},
+            {"name": "tool", "manifest_path": "/ws/tool/Cargo.toml", "dependencies": []}
+        ]
+    }"#;
+
+    fn names(packages: &[&str]) -> Option<BTreeSet<String>> {
+        Some(packages.iter().map(|p| p.to_string()).collect())
+    }
+
+    #[test]
+    fn test_affected_packages_include_dependents() {
+        let workspace = Workspace::from_metadata(METADATA).unwrap();
+
+        assert_eq!(workspace.affected_packages(Path::new("/ws/core/src/lib.rs")), names(&["client", "core", "server"]));
+        assert_eq!(workspace.affected_packages(Path::new("/ws/server/src/main.rs")), names(&["server"]));
+        assert_eq!(workspace.affected_packages(Path::new("/ws/tool/Cargo.toml")), names(&["tool"]));
+    }
+
+    #[test]
+    fn test_workspace_files_are_not_mapped() {
+        let workspace = Workspace::from_metadata(METADATA).unwrap();
+
+        assert_eq!(workspace.affected_packages(Path::new("/ws/Cargo.lock")), None);
+        assert_eq!(workspace.affected_packages(Path::new("/ws/Cargo.toml")), None);
+        assert_eq!(workspace.affected_packages(Path::new("/ws/README.md")), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! Select the tests affected by the files written in a session.
+
+mod cargo;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use glob::{MatchOptions, Pattern};
+
+use crate::{
+    error::ClientError,
+    project::{ImpactMapping, Project},
+    types::TestSelection,
+};
+
+use cargo::Workspace;
+
+const DEFAULT_CARGO_COMMAND: &str = "cargo test {targets}";
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn matches(mapping: &ImpactMapping, path: &Path) -> bool {
+    mapping.paths.iter()
+        .filter_map(|glob| Pattern::new(glob).ok())
+        .any(|pattern| pattern.matches_path_with(path, GLOB_OPTIONS))
+}
+
+/// Choose the tests to run for the files written in a session.
+///
+/// Falls back to the project's full `test_command` (saying why) whenever a
+/// written file can't be mapped to test targets.
+pub(crate) async fn select_tests(project: &Project, written: &[PathBuf]) -> Result<TestSelection, ClientError> {
+    let test_command = project.test_command
+        .clone()
+        .ok_or(ClientError::TestCommandNotConfigured)?;
+    let full = |reason: String| Ok(TestSelection {
+        command: test_command.clone(),
+        targets: Vec::new(),
+        fallback_reason: Some(reason),
+    });
+
+    let impact = &project.impact;
+    if impact.mappings.is_empty() && !impact.cargo_workspace {
+        return full("impact selection is not configured".to_string());
+    }
+    let command = match (&impact.command, impact.cargo_workspace) {
+        (Some(command), _) => command.clone(),
+        (None, true) => DEFAULT_CARGO_COMMAND.to_string(),
+        (None, false) => return full("no impact command is configured".to_string()),
+    };
+    if written.is_empty() {
+        return full("no files were written in this session".to_string());
+    }
+
+    let mut targets = BTreeSet::new();
+    let mut unmapped = Vec::new();
+    for path in written {
+        let relative = path.strip_prefix(&project.base_path).unwrap_or(path);
+        let mapped: Vec<_> = impact.mappings.iter().filter(|m| matches(m, relative)).collect();
+        if mapped.is_empty() {
+            unmapped.push(path);
+        }
+        for mapping in mapped {
+            targets.extend(mapping.targets.iter().cloned());
+        }
+    }
+
+    if let Some(path) = unmapped.first().filter(|_| !impact.cargo_workspace) {
+        return full(format!("{} does not match any impact mapping", path.display()));
+    }
+    if !unmapped.is_empty() {
+        let workspace = match Workspace::load(project).await {
+            Ok(workspace) => workspace,
+            Err(error) => return full(format!("could not read the Cargo workspace: {}", error)),
+        };
+        for path in unmapped {
+            let Some(packages) = workspace.affected_packages(path) else {
+                return full(format!("{} is not part of a single workspace package", path.display()));
+            };
+            targets.extend(packages.into_iter().map(|name| format!("-p {}", name)));
+        }
+    }
+
+    let targets: Vec<String> = targets.into_iter().collect();
+    Ok(TestSelection {
+        command: command.replace("{targets}", &targets.join(" ")),
+        targets,
+        fallback_reason: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ImpactConfig;
+
+    fn project() -> Project {
+        let mut project = Project::new(PathBuf::from("/repo"));
+        project.test_command = Some("make test".to_string());
+        project.impact = ImpactConfig {
+            command: Some("make test-only {targets}".to_string()),
+            mappings: vec![
+                ImpactMapping {
+                    paths: vec!["src/parser/**".to_string(), "grammar/*.g".to_string()],
+                    targets: vec!["parser".to_string()],
+                },
+                ImpactMapping {
+                    paths: vec!["src/*.rs".to_string()],
+                    targets: vec!["core".to_string()],
+                },
+            ],
+            cargo_workspace: false,
+        };
+        project
+    }
+
+    #[tokio::test]
+    async fn test_select_mapped_targets() {
+        let written = [
+            PathBuf::from("/repo/src/parser/lexer/mod.rs"),
+            PathBuf::from("/repo/grammar/expr.g"),
+            PathBuf::from("/repo/src/lib.rs"),
+        ];
+        let selection = select_tests(&project(), &written).await.unwrap();
+
+        assert_eq!(selection.targets, vec!["core", "parser"]);
+        assert_eq!(selection.command, "make test-only core parser");
+        assert_eq!(selection.fallback_reason, None);
+    }
+
+    #[tokio::test]
+    async fn test_unmapped_file_falls_back_to_full_suite() {
+        // `*` doesn't cross directories
+        let written = [PathBuf::from("/repo/src/parser/mod.rs"), PathBuf::from("/repo/src/util/io.rs")];
+        let selection = select_tests(&project(), &written).await.unwrap();
+
+        assert_eq!(selection.command, "make test");
+        assert!(selection.targets.is_empty());
+        assert_eq!(selection.fallback_reason.as_deref(), Some("/repo/src/util/io.rs does not match any impact mapping"));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_without_writes_or_config() {
+        let selection = select_tests(&project(), &[]).await.unwrap();
+        assert_eq!(selection.command, "make test");
+        assert_eq!(selection.fallback_reason.as_deref(), Some("no files were written in this session"));
+
+        let mut project = project();
+        project.impact = ImpactConfig::default();
+        let selection = select_tests(&project, &[PathBuf::from("/repo/src/lib.rs")]).await.unwrap();
+        assert_eq!(selection.fallback_reason.as_deref(), Some("impact selection is not configured"));
+
+        project.test_command = None;
+        assert!(matches!(select_tests(&project, &[]).await, Err(ClientError::TestCommandNotConfigured)));
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    collections::HashMap,
+    sync::{atomic::{AtomicU64, Ordering}, Arc},
+    time::{Duration, SystemTime},
+};
+use parking_lot::Mutex;
+use tokio::{sync::mpsc, task::JoinHandle};
+use codem_core::{command::run_command_with_options, types::{CommandOptions, ResourceLimit}, CommandError};
+use crate::{
+    error::ClientError,
+    types::{JobInfo, JobOutput, JobStatus},
+};
+
+/// How much memory jobs may hold on to
+#[derive(Debug, Clone, Copy)]
+pub struct JobLimits {
+    /// Output kept per job; older output is discarded as new output arrives
+    pub buffer_bytes: usize,
+    /// How long a finished job is kept after it finished
+    pub finished_ttl: Duration,
+    /// Finished jobs kept at most; the oldest are discarded first
+    pub max_finished: usize,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            buffer_bytes: 1024 * 1024,
+            finished_ttl: Duration::from_secs(60 * 60),
+            max_finished: 50,
+        }
+    }
+}
+
+/// Background commands started by sessions.
+///
+/// Jobs live in memory only; they are not restored when the client restarts.
+/// Finished jobs are discarded once they pass the limits' TTL or count.
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, Arc<Job>>>,
+    next_id: AtomicU64,
+    limits: JobLimits,
+}
+
+struct Job {
+    id: String,
+    session_id: String,
+    command: String,
+    started_at: SystemTime,
+    state: Mutex<JobState>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct JobState {
+    status: JobStatus,
+    /// The most recent output, up to the buffer limit
+    output: String,
+    /// Bytes discarded from the front of `output`; offsets count from the
+    /// start of all output, so they stay valid as it's discarded
+    discarded: usize,
+    tail_offset: usize,
+    finished_at: Option<SystemTime>,
+}
+
+impl JobState {
+    /// Append output, discarding the oldest if the buffer is full
+    fn push(&mut self, text: &str, buffer_bytes: usize) {
+        self.output.push_str(text);
+        if self.output.len() > buffer_bytes {
+            let mut cut = self.output.len() - buffer_bytes;
+            while !self.output.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.output.drain(..cut);
+            self.discarded += cut;
+        }
+    }
+
+    /// Offset just past the last byte of output
+    fn end(&self) -> usize {
+        self.discarded + self.output.len()
+    }
+}
+
+impl Job {
+    fn info(&self) -> JobInfo {
+        let state = self.state.lock();
+        JobInfo {
+            id: self.id.clone(),
+            command: self.command.clone(),
+            status: state.status.clone(),
+            started_at: self.started_at,
+            finished_at: state.finished_at,
+            output_len: state.end(),
+        }
+    }
+
+    /// Record the final status, unless the job already finished (e.g. was killed)
+    fn finish(&self, status: JobStatus) {
+        let mut state = self.state.lock();
+        if state.status == JobStatus::Running {
+            state.status = status;
+            state.finished_at = Some(SystemTime::now());
+        }
+    }
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        Self::with_limits(JobLimits::default())
+    }
+
+    pub fn with_limits(limits: JobLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Discard finished jobs past their TTL, then the oldest beyond the
+    /// number kept
+    fn prune(&self, jobs: &mut HashMap<String, Arc<Job>>) {
+        let now = SystemTime::now();
+        let mut finished: Vec<_> = jobs.values()
+            .filter_map(|job| job.state.lock().finished_at.map(|at| (at, job.id.clone())))
+            .collect();
+        finished.sort();
+
+        let expired = finished.iter()
+            .filter(|(at, _)| now.duration_since(*at).unwrap_or_default() >= self.limits.finished_ttl)
+            .count();
+        let excess = finished.len().saturating_sub(self.limits.max_finished);
+        for (_, id) in &finished[..expired.max(excess)] {
+            jobs.remove(id);
+        }
+    }
+
+    pub fn start(&self, session_id: &str, command: &str, mut options: CommandOptions) -> JobInfo {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+
+        let job = Arc::new(Job {
+            id: id.clone(),
+            session_id: session_id.to_string(),
+            command: command.to_string(),
+            started_at: SystemTime::now(),
+            state: Mutex::new(JobState {
+                status: JobStatus::Running,
+                output: String::new(),
+                discarded: 0,
+                tail_offset: 0,
+                finished_at: None,
+            }),
+            handle: Mutex::new(None),
+        });
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        options.output = Some(sender);
+
+        let buffer_bytes = self.limits.buffer_bytes;
+        let collector = tokio::spawn({
+            let job = job.clone();
+            async move {
+                while let Some(chunk) = receiver.recv().await {
+                    job.state.lock().push(&chunk.text, buffer_bytes);
+                }
+            }
+        });
+
+        let handle = tokio::spawn({
+            let job = job.clone();
+            let command = command.to_string();
+            async move {
+                let result = run_command_with_options(&command, &options).await;
+
+                // Make sure all output is collected before the job reports as finished
+                drop(options);
+                let _ = collector.await;
+
+                let limits_hit = match &result {
+                    Ok(output) => output.limits_hit.as_slice(),
+                    Err(CommandError::Timeout { output, .. } | CommandError::LimitExceeded { output, .. }) => {
+                        output.limits_hit.as_slice()
+                    }
+                    Err(_) => &[],
+                };
+                if limits_hit.contains(&ResourceLimit::Output) {
+                    job.state.lock().push("\n[output truncated: output size limit reached]\n", buffer_bytes);
+                }
+
+                job.finish(match result {
+                    Ok(output) => JobStatus::Exited { exit_code: output.exit_code },
+                    Err(CommandError::Timeout { .. }) => JobStatus::Killed,
+                    Err(CommandError::LimitExceeded { limit, .. }) => JobStatus::Failed {
+                        error: format!("killed after exceeding its {}", limit),
+                    },
+                    Err(e) => JobStatus::Failed { error: e.to_string() },
+                });
+            }
+        });
+        *job.handle.lock() = Some(handle);
+
+        let info = job.info();
+        let mut jobs = self.jobs.lock();
+        self.prune(&mut jobs);
+        jobs.insert(id, job);
+        info
+    }
+
+    fn get(&self, session_id: &str, job_id: &str) -> Result<Arc<Job>, ClientError> {
+        self.jobs.lock()
+            .get(job_id)
+            .filter(|job| job.session_id == session_id)
+            .cloned()
+            .ok_or_else(|| ClientError::JobNotFound { id: job_id.to_string() })
+    }
+
+    pub fn status(&self, session_id: &str, job_id: &str) -> Result<JobInfo, ClientError> {
+        Ok(self.get(session_id, job_id)?.info())
+    }
+
+    pub fn list(&self, session_id: &str) -> Vec<JobInfo> {
+        let mut jobs = self.jobs.lock();
+        self.prune(&mut jobs);
+        let mut jobs: Vec<_> = jobs
+            .values()
+            .filter(|job| job.session_id == session_id)
+            .map(|job| job.info())
+            .collect();
+        jobs.sort_by_key(|job| job.started_at);
+        jobs
+    }
+
+    /// Return output produced since the last tail (or since `offset`, if
+    /// given), as far as it's still in the buffer
+    pub fn tail(&self, session_id: &str, job_id: &str, offset: Option<usize>) -> Result<JobOutput, ClientError> {
+        let job = self.get(session_id, job_id)?;
+        let mut state = job.state.lock();
+
+        let offset = offset.unwrap_or(state.tail_offset).min(state.end());
+        let mut start = offset.saturating_sub(state.discarded);
+        while !state.output.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        let output = state.output[start..].to_string();
+        state.tail_offset = state.end();
+
+        Ok(JobOutput {
+            status: state.status.clone(),
+            output,
+            offset: state.tail_offset,
+            skipped: state.discarded.saturating_sub(offset),
+        })
+    }
+
+    pub fn kill(&self, session_id: &str, job_id: &str) -> Result<JobInfo, ClientError> {
+        let job = self.get(session_id, job_id)?;
+
+        // Dropping the running command terminates its whole process group
+        if let Some(handle) = job.handle.lock().take() {
+            handle.abort();
+        }
+        job.finish(JobStatus::Killed);
+
+        Ok(job.info())
+    }
+}
@@ -1,8 +1,15 @@
+pub mod approval;
 pub mod client;
 pub mod config;
 pub mod error;
+mod history;
+mod impact;
+mod jobs;
 pub mod project;
+pub mod safety;
 mod session;
+mod test_cache;
+pub mod test_results;
 pub mod types;
 
 #[cfg(test)]
@@ -11,7 +18,7 @@ mod tests;
 // Re-export main types
 pub use client::Client;
 pub use config::ClientConfig;
-pub use project::Project;
+pub use project::{ApprovalConfig, ImpactConfig, ImpactMapping, PatternMode, Project, SandboxConfig};
 pub use error::ClientError;
 pub use session::{SessionId, SessionInfo};
 pub use session::manager::SessionManager;
\ No newline at end of file
@@ -1,12 +1,137 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use codem_core::{
+    redact::Redactor,
+    types::{CommandEnv, CommandOptions, ResourceLimits, Sandbox},
+};
 use serde::{Deserialize, Serialize};
 
+use crate::error::ConfigError;
+use crate::types::{CheckStage, TestFormat};
+
+/// How a project's command patterns combine with the global ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternMode {
+    /// Project patterns are checked in addition to the global patterns
+    #[default]
+    Extend,
+    /// Project patterns replace the global patterns entirely
+    Override,
+}
+
+/// Whether commands run in a sandbox that only lets them write inside the project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    pub enabled: bool,
+    pub allow_network: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_network: true,
+        }
+    }
+}
+
+/// Whether risky commands need a human's approval before they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalConfig {
+    pub required: bool,
+    /// How long `run_command_risky` waits for a decision before returning
+    /// the request as pending
+    pub wait_ms: u64,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            required: false,
+            wait_ms: 30_000,
+        }
+    }
+}
+
+/// Which tests are affected by a change to some files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImpactMapping {
+    /// Globs relative to `base_path`, e.g. `src/parser/**`
+    pub paths: Vec<String>,
+    /// Substituted for `{targets}` in the impact command
+    pub targets: Vec<String>,
+}
+
+/// How to run only the tests affected by the files written in a session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImpactConfig {
+    /// Command running a subset of the tests; `{targets}` is replaced by the
+    /// selected targets, separated by spaces. Defaults to `cargo test {targets}`
+    /// when `cargo_workspace` is set.
+    pub command: Option<String>,
+    /// Checked first; a file can match several mappings
+    pub mappings: Vec<ImpactMapping>,
+    /// Map files not covered by `mappings` to their Cargo package, selecting
+    /// it and every workspace package depending on it as `-p <name>`
+    pub cargo_workspace: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub name: String,
     pub base_path: PathBuf,
     pub allowed_paths: Option<Vec<PathBuf>>,
     pub test_command: Option<String>,
+    /// Format of the test command's output; detected if not set
+    #[serde(default)]
+    pub test_format: Option<TestFormat>,
+    /// JUnit XML report written by the test command, relative to `base_path`.
+    /// If set, results are read from it rather than from the output.
+    #[serde(default)]
+    pub test_report_path: Option<PathBuf>,
+    /// Check stages, run in order by `run_checks`
+    #[serde(default)]
+    pub checks: Vec<CheckStage>,
+    /// Skip the remaining stages once one fails
+    #[serde(default)]
+    pub stop_checks_on_failure: bool,
+    /// Run the checks after every successful write
+    #[serde(default)]
+    pub checks_after_write: bool,
+    #[serde(default)]
+    pub impact: ImpactConfig,
+    /// Patterns for commands that are safe in this project
+    #[serde(default)]
+    pub safe_patterns: Vec<String>,
+    /// Patterns for commands that are risky in this project
+    #[serde(default)]
+    pub risky_patterns: Vec<String>,
+    #[serde(default)]
+    pub pattern_mode: PatternMode,
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    /// Refresh the session's timestamps of files a command changes, so
+    /// they can be written without being read again
+    #[serde(default)]
+    pub refresh_changed_files: bool,
+    /// Resource limits applied to every command run in this project
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    /// Environment variables for commands run in this project
+    #[serde(default)]
+    pub env: CommandEnv,
+    /// Values masked in command output, e.g. tokens the commands have access to
+    #[serde(default)]
+    pub secrets: Vec<String>,
+    /// Patterns for secrets masked in command output
+    #[serde(default)]
+    pub secret_patterns: Vec<String>,
 }
 
 impl Project {
@@ -16,6 +141,54 @@ impl Project {
             base_path,
             allowed_paths: None,
             test_command: None,
+            test_format: None,
+            test_report_path: None,
+            checks: Vec::new(),
+            stop_checks_on_failure: false,
+            checks_after_write: false,
+            impact: ImpactConfig::default(),
+            safe_patterns: Vec::new(),
+            risky_patterns: Vec::new(),
+            pattern_mode: PatternMode::default(),
+            approval: ApprovalConfig::default(),
+            refresh_changed_files: false,
+            limits: ResourceLimits::default(),
+            sandbox: SandboxConfig::default(),
+            env: CommandEnv::default(),
+            secrets: Vec::new(),
+            secret_patterns: Vec::new(),
         }
     }
-}
\ No newline at end of file
+
+    /// Options for running a command in this project: limits, sandbox,
+    /// environment and secret redaction
+    pub fn command_options(&self, cwd: &Path) -> Result<CommandOptions, ConfigError> {
+        let redactor = Redactor::new(&self.secrets, &self.secret_patterns)
+            .map_err(|e| ConfigError::InvalidPattern { pattern: e.to_string() })?;
+
+        Ok(CommandOptions {
+            cwd: Some(cwd.to_path_buf()),
+            limits: self.limits,
+            sandbox: self.command_sandbox(),
+            env: self.env.clone(),
+            redactor: redactor.map(Arc::new),
+            ..Default::default()
+        })
+    }
+
+    /// The sandbox commands in this project run in, if it is enabled.
+    /// Only `base_path` and `allowed_paths` are writable.
+    pub fn command_sandbox(&self) -> Option<Sandbox> {
+        if !self.sandbox.enabled {
+            return None;
+        }
+
+        let mut writable_paths = vec![self.base_path.clone()];
+        writable_paths.extend(self.allowed_paths.iter().flatten().cloned());
+
+        Some(Sandbox {
+            writable_paths,
+            allow_network: self.sandbox.allow_network,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+pub mod shell;
+
+use std::fmt;
+use regex::Regex;
+
+use shell::parse_command;
+
+/// Why a command was not considered safe to run without confirmation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnsafeReason {
+    /// The command line could not be split into simple commands
+    Unparseable { error: String },
+    /// The command line doesn't run anything
+    Empty,
+    /// A segment (or the whole command line) matched a risky pattern
+    RiskyPattern { segment: String, pattern: String },
+    /// A segment didn't match any safe pattern
+    NoSafePattern { segment: String },
+    /// A segment redirects output into a file
+    FileRedirection { segment: String, target: String },
+}
+
+impl fmt::Display for UnsafeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsafeReason::Unparseable { error } => write!(f, "could not parse command: {}", error),
+            UnsafeReason::Empty => write!(f, "command is empty"),
+            UnsafeReason::RiskyPattern { segment, pattern } => {
+                write!(f, "'{}' matches risky pattern '{}'", segment, pattern)
+            }
+            UnsafeReason::NoSafePattern { segment } => {
+                write!(f, "'{}' does not match any safe pattern", segment)
+            }
+            UnsafeReason::FileRedirection { segment, target } => {
+                write!(f, "'{}' redirects output to file '{}'", segment, target)
+            }
+        }
+    }
+}
+
+fn find_match<'a>(patterns: &'a [String], text: &str) -> Option<&'a String> {
+    patterns.iter().find(|p| Regex::new(p).is_ok_and(|re| re.is_match(text)))
+}
+
+/// Check a command line against safe and risky patterns.
+///
+/// The command is split into every simple command it would run (across
+/// `&&`, `||`, `;`, pipes, subshells and command substitutions). It is safe
+/// only if every one of them matches a safe pattern, none of them (nor the
+/// full command line) matches a risky pattern, and no output is redirected
+/// into a file.
+pub fn check_command(
+    command: &str,
+    safe_patterns: &[String],
+    risky_patterns: &[String],
+) -> Result<(), UnsafeReason> {
+    if let Some(pattern) = find_match(risky_patterns, command) {
+        return Err(UnsafeReason::RiskyPattern {
+            segment: command.to_string(),
+            pattern: pattern.clone(),
+        });
+    }
+
+    let segments = parse_command(command).map_err(|error| UnsafeReason::Unparseable { error })?;
+    if segments.is_empty() {
+        return Err(UnsafeReason::Empty);
+    }
+
+    for segment in &segments {
+        let text = segment.text();
+
+        if let Some(redirection) = segment.redirections.iter().find(|r| r.writes_file()) {
+            return Err(UnsafeReason::FileRedirection {
+                segment: text,
+                target: redirection.target.clone(),
+            });
+        }
+
+        // A segment that is only a redirection (e.g. `< file`) runs nothing
+        if segment.words.is_empty() {
+            continue;
+        }
+
+        if let Some(pattern) = find_match(risky_patterns, &text) {
+            return Err(UnsafeReason::RiskyPattern {
+                segment: text,
+                pattern: pattern.clone(),
+            });
+        }
+
+        if find_match(safe_patterns, &text).is_none() {
+            return Err(UnsafeReason::NoSafePattern { segment: text });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,382 @@
+//! Minimal POSIX shell tokenizer used for command safety checks.
+//!
+//! This is not a full shell parser. It understands just enough to split a
+//! command line into the simple commands that will actually be executed:
+//! list/pipeline operators, quoting, redirections, subshells and command
+//! substitutions. Anything it does not understand is reported as an error so
+//! the caller can treat the command as unsafe.
+
+/// A redirection attached to a simple command, e.g. `2>&1` or `> out.txt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirection {
+    pub operator: String,
+    pub target: String,
+}
+
+impl Redirection {
+    /// Whether this redirection can create or overwrite a file
+    pub fn writes_file(&self) -> bool {
+        if !self.operator.contains('>') {
+            return false;
+        }
+
+        // `>&2`, `2>&1`, `>&-` just duplicate or close file descriptors
+        let is_fd_dup = self.operator.ends_with('&')
+            && (self.target == "-" || self.target.chars().all(|c| c.is_ascii_digit()));
+
+        !is_fd_dup && self.target != "/dev/null"
+    }
+}
+
+/// A single simple command, without the operators that connect it to others
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Segment {
+    pub words: Vec<String>,
+    pub redirections: Vec<Redirection>,
+}
+
+impl Segment {
+    /// The command words joined by single spaces, as matched against patterns
+    pub fn text(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+/// Split a command line into every simple command it would run.
+///
+/// Subshells and command substitutions contribute their inner commands as
+/// additional segments.
+pub fn parse_command(command: &str) -> Result<Vec<Segment>, String> {
+    Parser::new(command).parse()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    segments: Vec<Segment>,
+    current: Segment,
+    word: String,
+    pending_redirect: Option<String>,
+}
+
+impl Parser {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+            segments: Vec::new(),
+            current: Segment::default(),
+            word: String::new(),
+            pending_redirect: None,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse(mut self) -> Result<Vec<Segment>, String> {
+        while let Some(c) = self.bump() {
+            match c {
+                '\n' | ';' => self.finish_segment()?,
+                ' ' | '\t' | '\r' => self.finish_word(),
+                '&' => {
+                    if self.word.is_empty() && self.eat('>') {
+                        // `&>` / `&>>` redirect both stdout and stderr
+                        let mut op = "&>".to_string();
+                        if self.eat('>') {
+                            op.push('>');
+                        }
+                        self.start_redirect(op)?;
+                    } else {
+                        self.eat('&');
+                        self.finish_segment()?;
+                    }
+                }
+                '|' => {
+                    if !self.eat('|') {
+                        self.eat('&');
+                    }
+                    self.finish_segment()?;
+                }
+                '>' | '<' => {
+                    let mut op = String::new();
+                    // A word made only of digits right before the operator is a file descriptor
+                    if !self.word.is_empty() && self.word.chars().all(|c| c.is_ascii_digit()) {
+                        op = std::mem::take(&mut self.word);
+                    } else {
+                        self.finish_word();
+                    }
+                    op.push(c);
+                    if c == '>' {
+                        if self.eat('>') {
+                            op.push('>');
+                        } else if self.eat('|') {
+                            op.push('|');
+                        }
+                    } else if self.eat('<') {
+                        op.push('<');
+                        if self.eat('<') {
+                            op.push('<');
+                        }
+                    } else if self.eat('>') {
+                        op.push('>');
+                    }
+                    if self.eat('&') {
+                        op.push('&');
+                    }
+                    self.start_redirect(op)?;
+                }
+                '(' => {
+                    if !self.word.is_empty() || !self.current.words.is_empty() || self.pending_redirect.is_some() {
+                        return Err("unexpected '('".to_string());
+                    }
+                    let inner = self.read_balanced()?;
+                    self.segments.extend(parse_command(&inner)?);
+                }
+                ')' => return Err("unmatched ')'".to_string()),
+                '$' if self.peek() == Some('(') => {
+                    self.pos += 1;
+                    let inner = self.read_balanced()?;
+                    self.segments.extend(parse_command(&inner)?);
+                    self.word.push_str(&format!("$({})", inner));
+                }
+                '`' => {
+                    let inner = self.read_backticks()?;
+                    self.segments.extend(parse_command(&inner)?);
+                    self.word.push_str(&format!("`{}`", inner));
+                }
+                '\'' => {
+                    self.word.push('\'');
+                    loop {
+                        match self.bump() {
+                            Some('\'') => break,
+                            Some(c) => self.word.push(c),
+                            None => return Err("unterminated single quote".to_string()),
+                        }
+                    }
+                    self.word.push('\'');
+                }
+                '"' => self.read_double_quoted()?,
+                '\\' => match self.bump() {
+                    // Line continuation
+                    Some('\n') => {}
+                    Some(c) => {
+                        self.word.push('\\');
+                        self.word.push(c);
+                    }
+                    None => return Err("trailing backslash".to_string()),
+                },
+                '#' if self.word.is_empty() => {
+                    while let Some(c) = self.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                c => self.word.push(c),
+            }
+        }
+
+        self.finish_segment()?;
+        Ok(self.segments)
+    }
+
+    fn start_redirect(&mut self, op: String) -> Result<(), String> {
+        if self.pending_redirect.is_some() {
+            return Err(format!("unexpected '{}'", op));
+        }
+        self.pending_redirect = Some(op);
+        Ok(())
+    }
+
+    fn finish_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        match self.pending_redirect.take() {
+            Some(operator) => self.current.redirections.push(Redirection { operator, target: word }),
+            None => self.current.words.push(word),
+        }
+    }
+
+    fn finish_segment(&mut self) -> Result<(), String> {
+        self.finish_word();
+        if let Some(op) = self.pending_redirect.take() {
+            return Err(format!("missing target for '{}'", op));
+        }
+
+        let mut segment = std::mem::take(&mut self.current);
+        // Braces only group commands, they don't run anything themselves
+        if segment.words.first().map(String::as_str) == Some("{") {
+            segment.words.remove(0);
+        }
+        if segment.words.last().map(String::as_str) == Some("}") {
+            segment.words.pop();
+        }
+
+        if !segment.words.is_empty() || !segment.redirections.is_empty() {
+            self.segments.push(segment);
+        }
+        Ok(())
+    }
+
+    /// Read up to the `)` matching an already consumed `(`, returning the text between them
+    fn read_balanced(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '\'' | '"' | '`' => {
+                    while let Some(q) = self.bump() {
+                        if q == '\\' && c != '\'' {
+                            self.bump();
+                        } else if q == c {
+                            break;
+                        }
+                    }
+                }
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(self.chars[start..self.pos - 1].iter().collect());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err("unmatched '('".to_string())
+    }
+
+    /// Read up to the closing backtick, returning the text in between
+    fn read_backticks(&mut self) -> Result<String, String> {
+        let mut inner = String::new();
+        loop {
+            match self.bump() {
+                Some('`') => return Ok(inner),
+                Some('\\') => {
+                    if let Some(c) = self.bump() {
+                        inner.push(c);
+                    }
+                }
+                Some(c) => inner.push(c),
+                None => return Err("unterminated backtick".to_string()),
+            }
+        }
+    }
+
+    /// Double quotes still allow command substitution, so look inside them
+    fn read_double_quoted(&mut self) -> Result<(), String> {
+        self.word.push('"');
+        loop {
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => {
+                    self.word.push('\\');
+                    if let Some(c) = self.bump() {
+                        self.word.push(c);
+                    }
+                }
+                Some('$') if self.peek() == Some('(') => {
+                    self.pos += 1;
+                    let inner = self.read_balanced()?;
+                    self.segments.extend(parse_command(&inner)?);
+                    self.word.push_str(&format!("$({})", inner));
+                }
+                Some('`') => {
+                    let inner = self.read_backticks()?;
+                    self.segments.extend(parse_command(&inner)?);
+                    self.word.push_str(&format!("`{}`", inner));
+                }
+                Some(c) => self.word.push(c),
+                None => return Err("unterminated double quote".to_string()),
+            }
+        }
+        self.word.push('"');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn texts(command: &str) -> Vec<String> {
+        parse_command(command).unwrap().iter().map(Segment::text).collect()
+    }
+
+    #[rstest]
+    #[case("cargo test", vec!["cargo test"])]
+    #[case("cargo test && rm -rf target", vec!["cargo test", "rm -rf target"])]
+    #[case("a; b || c | d & e", vec!["a", "b", "c", "d", "e"])]
+    #[case("a |& b", vec!["a", "b"])]
+    #[case("a\nb", vec!["a", "b"])]
+    #[case("echo 'a && b'", vec!["echo 'a && b'"])]
+    #[case("echo \"a; b\"", vec!["echo \"a; b\""])]
+    #[case("echo a\\;b", vec!["echo a\\;b"])]
+    #[case("(cd x && make)", vec!["cd x", "make"])]
+    #[case("{ a; b; }", vec!["a", "b"])]
+    #[case("echo $(rm -rf /)", vec!["rm -rf /", "echo $(rm -rf /)"])]
+    #[case("echo \"$(whoami)\"", vec!["whoami", "echo \"$(whoami)\""])]
+    #[case("echo `id`", vec!["id", "echo `id`"])]
+    #[case("ls # && rm", vec!["ls"])]
+    fn test_segments(#[case] command: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(texts(command), expected);
+    }
+
+    #[test]
+    fn test_redirections() {
+        let segments = parse_command("cargo test 2>&1 >out.txt < in.txt &>> all.log").unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text(), "cargo test");
+
+        let redirections: Vec<_> = segments[0].redirections.iter()
+            .map(|r| (r.operator.as_str(), r.target.as_str(), r.writes_file()))
+            .collect();
+        assert_eq!(redirections, vec![
+            ("2>&", "1", false),
+            (">", "out.txt", true),
+            ("<", "in.txt", false),
+            ("&>>", "all.log", true),
+        ]);
+    }
+
+    #[test]
+    fn test_dev_null_is_not_a_file_write() {
+        let segments = parse_command("cargo build > /dev/null 2>&1").unwrap();
+        assert!(segments[0].redirections.iter().all(|r| !r.writes_file()));
+    }
+
+    #[rstest]
+    #[case("echo 'unterminated")]
+    #[case("echo \"unterminated")]
+    #[case("(echo")]
+    #[case("echo )")]
+    #[case("echo $(ls")]
+    #[case("echo >")]
+    fn test_parse_errors(#[case] command: &str) {
+        assert!(parse_command(command).is_err());
+    }
+}
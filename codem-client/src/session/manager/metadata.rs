@@ -3,7 +3,7 @@ use tokio::fs;
 use std::io;
 use crate::error::ClientError;
 use std::time::SystemTime;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use serde::{Serialize, Deserialize};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -23,28 +23,28 @@ impl From<SerializableTimestamp> for SystemTime {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct SessionData {
-    project: String,
-    files: HashMap<String, i64>,
-}
-
 #[derive(Clone)]
 pub struct Metadata {
     file: PathBuf,
     pub(crate) timestamps: HashMap<String, HashMap<PathBuf, SystemTime>>,
     pub(crate) projects: HashMap<String, String>, // session_id -> project_name
+    pub(crate) written: HashMap<String, BTreeSet<PathBuf>>, // session_id -> files written
 }
 
 impl Metadata {
     pub async fn new(file: PathBuf) -> Self {
         tracing::info!("Loading session file: {}", file.display());
-        let (timestamps, projects) = Self::load_file(&file).await.unwrap_or_default();
+        let (timestamps, projects, written) = Self::load_file(&file).await.unwrap_or_default();
         tracing::info!("Loaded sessions: {:?}", projects.keys().collect::<Vec<_>>());
-        Self { file, timestamps, projects }
+        Self { file, timestamps, projects, written }
     }
 
-    async fn load_file(path: &Path) -> io::Result<(HashMap<String, HashMap<PathBuf, SystemTime>>, HashMap<String, String>)> {
+    #[allow(clippy::type_complexity)]
+    async fn load_file(path: &Path) -> io::Result<(
+        HashMap<String, HashMap<PathBuf, SystemTime>>,
+        HashMap<String, String>,
+        HashMap<String, BTreeSet<PathBuf>>,
+    )> {
         let contents = match fs::read_to_string(path).await {
             Ok(c) => {
                 tracing::info!("Successfully read session file");
@@ -52,7 +52,7 @@ impl Metadata {
             },
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 tracing::warn!("Session file not found: {}", path.display());
-                return Ok((HashMap::new(), HashMap::new()))
+                return Ok((HashMap::new(), HashMap::new(), HashMap::new()))
             },
             Err(e) => {
                 tracing::error!("Failed to read session file: {}", e);
@@ -68,13 +68,14 @@ impl Metadata {
             },
             Err(e) => {
                 tracing::error!("Failed to parse session TOML: {}\nContents: {}", e, contents);
-                return Ok((HashMap::new(), HashMap::new()));
+                return Ok((HashMap::new(), HashMap::new(), HashMap::new()));
             }
         };
 
         // Convert into our desired types
         let mut timestamps = HashMap::new();
         let mut projects = HashMap::new();
+        let mut written = HashMap::new();
 
         for (session_id, data) in toml_str {
             if let Some(data) = data.as_table() {
@@ -105,13 +106,21 @@ impl Metadata {
                 } else {
                     tracing::warn!("No files found for session {}", session_id);
                 }
+
+                // Get files written in this session
+                if let Some(files) = data.get("written").and_then(|v| v.as_array()) {
+                    let session_written: BTreeSet<PathBuf> = files.iter()
+                        .filter_map(|path| path.as_str().map(PathBuf::from))
+                        .collect();
+                    written.insert(session_id.clone(), session_written);
+                }
             } else {
                 tracing::warn!("Session {} data is not a table", session_id);
             }
         }
 
         tracing::info!("Finished loading {} sessions", projects.len());
-        Ok((timestamps, projects))
+        Ok((timestamps, projects, written))
     }
 
     async fn save_file(&self) -> Result<(), ClientError> {
@@ -135,6 +144,9 @@ impl Metadata {
                 .collect();
 
             session_table.insert("files".to_string(), toml::Value::try_from(files)?);
+            if let Some(written) = self.written.get(session_id) {
+                session_table.insert("written".to_string(), toml::Value::try_from(written)?);
+            }
             root.insert(session_id.clone(), toml::Value::Table(session_table));
         }
 
@@ -164,15 +176,33 @@ impl Metadata {
     pub fn get_timestamp(&self, session_id: &str, path: &Path) -> Result<SystemTime, ClientError> {
         let path = path.to_path_buf();
         let session_stamps = self.timestamps.get(session_id)
-            .ok_or_else(|| ClientError::FileNotSynced { content: None })?;
+            .ok_or(ClientError::FileNotSynced { content: None })?;
 
         session_stamps.get(&path)
             .cloned()
-            .ok_or_else(|| ClientError::FileNotSynced { 
+            .ok_or(ClientError::FileNotSynced { 
                 content: None 
             })
     }
 
+    /// Files written in the session, in path order
+    pub fn get_written_files(&self, session_id: &str) -> Vec<PathBuf> {
+        self.written.get(session_id)
+            .map(|files| files.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn record_write(&mut self, session_id: &str, path: &Path) -> Result<(), ClientError> {
+        let inserted = self.written
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(path.to_path_buf());
+        if inserted {
+            self.save_file().await?;
+        }
+        Ok(())
+    }
+
     pub async fn update_session(&mut self, session_id: &str, project: &str, path: &Path, timestamp: SystemTime) -> Result<(), ClientError> {
         self.projects.insert(session_id.to_string(), project.to_string());
         
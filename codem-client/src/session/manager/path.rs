@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use crate::{error::ClientError, config::ClientConfig};
 
 #[derive(Clone)]
@@ -31,9 +31,9 @@ impl PathValidator {
     pub fn validate_path(&self, path: &Path) -> Result<(), ClientError> {
         // Convert to absolute path if relative
         let path = if path.is_absolute() {
-            path.to_path_buf()
+            normalize(path)
         } else {
-            std::env::current_dir()?.join(path)
+            normalize(&std::env::current_dir()?.join(path))
         };
 
         // Check if path is within any allowed directory
@@ -58,3 +58,19 @@ impl PathValidator {
         })
     }
 }
+
+/// Resolve `.` and `..` components without touching the filesystem, so a
+/// path like `base/../elsewhere` isn't mistaken for one inside `base`
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc, time::SystemTime};
+use std::{collections::HashMap, path::Path, sync::Arc, time::SystemTime};
 use tokio::sync::Mutex;
 use crate::{
     error::ClientError,
@@ -119,6 +119,32 @@ impl Session {
         metadata.update_session(&self.id, &self.project.name, path, timestamp).await
     }
 
+    /// Remember that a file was written in this session
+    pub async fn record_write(&self, path: &Path) -> Result<(), ClientError> {
+        let mut metadata = self.metadata.lock().await;
+        metadata.record_write(&self.id, path).await
+    }
+
+    /// Files whose timestamps the session has recorded, i.e. that it has read or written
+    pub async fn tracked_files(&self) -> Vec<std::path::PathBuf> {
+        self.metadata.lock().await.get_session_timestamps(&self.id).into_keys().collect()
+    }
+
+    /// Timestamps recorded for the files the session has read or written
+    pub async fn recorded_timestamps(&self) -> HashMap<std::path::PathBuf, SystemTime> {
+        self.metadata.lock().await.get_session_timestamps(&self.id)
+    }
+
+    /// Timestamp recorded when the file was last read or written, without
+    /// checking it against the file
+    pub async fn recorded_timestamp(&self, path: &Path) -> Option<SystemTime> {
+        self.metadata.lock().await.get_timestamp(&self.id, path).ok()
+    }
+
+    pub async fn written_files(&self) -> Vec<std::path::PathBuf> {
+        self.metadata.lock().await.get_written_files(&self.id)
+    }
+
     pub async fn to_info(&self) -> SessionInfo {
         SessionInfo {
             id: SessionId(self.id.clone()),
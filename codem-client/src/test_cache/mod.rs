@@ -0,0 +1,181 @@
+//! Results of test runs, reused while the files a session has touched are unchanged.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    time::SystemTime,
+};
+use parking_lot::Mutex;
+use tokio::fs;
+use crate::{
+    error::ClientError,
+    project::Project,
+    session::manager::session::Session,
+    types::{TestReport, TestRun},
+};
+
+/// The latest result of each test command, by project and the settings it
+/// ran with
+#[derive(Default)]
+pub struct TestCache {
+    entries: Mutex<HashMap<(String, String, u64), CachedTests>>,
+}
+
+struct CachedTests {
+    fingerprint: u64,
+    ran_at: SystemTime,
+    outcome: Outcome,
+}
+
+/// Only finished runs are cached; timeouts and other errors are not
+enum Outcome {
+    Passed(TestRun),
+    Failed {
+        stdout: String,
+        stderr: String,
+        exit_code: i32,
+        report: Option<Box<TestReport>>,
+    },
+}
+
+impl TestCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The previous result of `command`, if it ran against the same files
+    pub fn get(&self, project: &Project, command: &str, fingerprint: u64) -> Option<Result<TestRun, ClientError>> {
+        let entries = self.entries.lock();
+        let cached = entries.get(&(project.name.clone(), command.to_string(), settings_key(project)))
+            .filter(|cached| cached.fingerprint == fingerprint)?;
+
+        Some(match &cached.outcome {
+            Outcome::Passed(run) => Ok(TestRun {
+                cached_at: Some(cached.ran_at),
+                ..run.clone()
+            }),
+            Outcome::Failed { stdout, stderr, exit_code, report } => Err(ClientError::TestCommandFailed {
+                stdout: stdout.clone(),
+                stderr: stderr.clone(),
+                exit_code: *exit_code,
+                report: report.clone(),
+                cached_at: Some(cached.ran_at),
+            }),
+        })
+    }
+
+    pub fn store(
+        &self,
+        project: &Project,
+        command: &str,
+        fingerprint: u64,
+        ran_at: SystemTime,
+        result: &Result<TestRun, ClientError>,
+    ) {
+        let key = (project.name.clone(), command.to_string(), settings_key(project));
+        let outcome = match result {
+            Ok(run) => Outcome::Passed(run.clone()),
+            Err(ClientError::TestCommandFailed { stdout, stderr, exit_code, report, .. }) => Outcome::Failed {
+                stdout: stdout.clone(),
+                stderr: stderr.clone(),
+                exit_code: *exit_code,
+                report: report.clone(),
+            },
+            Err(_) => {
+                self.entries.lock().remove(&key);
+                return;
+            }
+        };
+        self.entries.lock().insert(key, CachedTests { fingerprint, ran_at, outcome });
+    }
+}
+
+/// Hash of the files the session has read or written: each one's path, the
+/// timestamp the session recorded for it, and its current size and
+/// modification time.
+///
+/// Only those files are looked at, so this stays cheap however large the
+/// project is, and covers ignored files such as `.env` once the session has
+/// read them. A change to a file the session never touched goes unnoticed;
+/// `no_cache` runs the tests regardless.
+pub async fn fingerprint(session: &Session) -> u64 {
+    let mut files: Vec<_> = session.recorded_timestamps().await.into_iter().collect();
+    files.sort();
+    fingerprint_files(&files).await
+}
+
+async fn fingerprint_files(files: &[(PathBuf, SystemTime)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (path, recorded) in files {
+        path.hash(&mut hasher);
+        recorded.hash(&mut hasher);
+        // Deleted files hash differently from any that exist
+        match fs::metadata(path).await {
+            Ok(metadata) => Some((metadata.len(), metadata.modified().ok())),
+            Err(_) => None,
+        }.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Hash of the project settings that can change a test run's result
+fn settings_key(project: &Project) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&(&project.env, &project.limits, &project.sandbox))
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_fingerprint_changes_with_tracked_files() {
+        let dir = TempDir::new().unwrap();
+        let lib = dir.path().join("lib.rs");
+        std::fs::write(&lib, "fn a() {}").unwrap();
+        let recorded = std::fs::metadata(&lib).unwrap().modified().unwrap();
+        let files = vec![(lib.clone(), recorded)];
+
+        let original = fingerprint_files(&files).await;
+        assert_eq!(fingerprint_files(&files).await, original);
+
+        // Changed outside the session
+        std::fs::write(&lib, "fn changed() {}").unwrap();
+        let changed = fingerprint_files(&files).await;
+        assert_ne!(changed, original);
+
+        std::fs::remove_file(&lib).unwrap();
+        assert_ne!(fingerprint_files(&files).await, changed);
+
+        let more = vec![(lib, recorded), (dir.path().join("new.rs"), recorded)];
+        assert_ne!(fingerprint_files(&more).await, fingerprint_files(&files).await);
+    }
+
+    #[test]
+    fn test_results_are_kept_apart_by_settings() {
+        let cache = TestCache::new();
+        let project = Project::new(PathBuf::from("/project"));
+        let run = TestRun {
+            exit_code: 0,
+            output: "ok".to_string(),
+            report: None,
+            cached_at: None,
+        };
+        cache.store(&project, "make test", 1, SystemTime::now(), &Ok(run));
+        assert!(cache.get(&project, "make test", 1).is_some());
+        assert!(cache.get(&project, "make test", 2).is_none());
+
+        let mut other_env = project.clone();
+        other_env.env.set.insert("MODE".to_string(), "release".to_string());
+        assert!(cache.get(&other_env, "make test", 1).is_none());
+
+        let mut other_limits = project.clone();
+        other_limits.limits.max_output_bytes = Some(1);
+        assert!(cache.get(&other_limits, "make test", 1).is_none());
+    }
+}
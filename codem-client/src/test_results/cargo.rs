@@ -0,0 +1,167 @@
+use std::sync::LazyLock;
+use regex::Regex;
+
+use super::{parse_seconds, TestParser};
+use crate::types::{TestCase, TestFormat, TestOutcome, TestReport};
+
+/// `test name ... ok`, optionally with a reason after `ignored` and a
+/// `<0.01s>` duration (with `--report-time`)
+static TEST_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^test (.+?) \.\.\. (ok|FAILED|ignored)(?:, .*?)?(?: <([\d.]+s)>)?$").unwrap()
+});
+
+static RESULT_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored;.*?(?:finished in ([\d.]+s))?$").unwrap()
+});
+
+static FAILURE_HEADER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^---- (.+?) stdout ----$").unwrap());
+
+/// libtest output, as printed by `cargo test`. Results of all test binaries
+/// in the output are added up.
+pub struct CargoParser;
+
+impl TestParser for CargoParser {
+    fn format(&self) -> TestFormat {
+        TestFormat::Cargo
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::new(TestFormat::Cargo);
+        let mut totals: Option<(usize, usize, usize)> = None;
+        let mut duration = None;
+
+        // Captured output of the failed test currently being read
+        let mut failure: Option<(String, Vec<&str>)> = None;
+        let mut messages = Vec::new();
+
+        for line in output.lines() {
+            if let Some(caps) = TEST_LINE.captures(line) {
+                report.tests.push(TestCase {
+                    name: caps[1].to_string(),
+                    outcome: match &caps[2] {
+                        "ok" => TestOutcome::Passed,
+                        "FAILED" => TestOutcome::Failed,
+                        _ => TestOutcome::Ignored,
+                    },
+                    duration_secs: caps.get(3).and_then(|d| parse_seconds(d.as_str())),
+                    message: None,
+                });
+                continue;
+            }
+
+            if let Some(caps) = RESULT_LINE.captures(line) {
+                let (passed, failed, ignored) = totals.unwrap_or_default();
+                totals = Some((
+                    passed + caps[1].parse::<usize>().unwrap_or(0),
+                    failed + caps[2].parse::<usize>().unwrap_or(0),
+                    ignored + caps[3].parse::<usize>().unwrap_or(0),
+                ));
+                if let Some(seconds) = caps.get(4).and_then(|d| parse_seconds(d.as_str())) {
+                    duration = Some(duration.unwrap_or(0.0) + seconds);
+                }
+                messages.extend(failure.take());
+                continue;
+            }
+
+            if let Some(caps) = FAILURE_HEADER.captures(line) {
+                messages.extend(failure.take());
+                failure = Some((caps[1].to_string(), Vec::new()));
+                continue;
+            }
+
+            // The list of failed test names after the captured output ends it
+            if line == "failures:" {
+                messages.extend(failure.take());
+                continue;
+            }
+
+            if let Some((_, lines)) = &mut failure {
+                lines.push(line);
+            }
+        }
+        messages.extend(failure);
+
+        if report.tests.is_empty() && totals.is_none() {
+            return None;
+        }
+
+        for (name, lines) in messages {
+            let message = lines.join("\n").trim().to_string();
+            if let Some(test) = report.tests.iter_mut().find(|t| t.name == name && t.message.is_none()) {
+                test.message = Some(message);
+            }
+        }
+
+        match totals {
+            Some((passed, failed, ignored)) => {
+                (report.passed, report.failed, report.ignored) = (passed, failed, ignored);
+            }
+            None => report.count_tests(),
+        }
+        report.duration_secs = duration;
+
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+running 4 tests
+test tests::passes ... ok
+test tests::fails ... FAILED
+test tests::skipped ... ignored
+test tests::slow ... ignored, takes too long
+
+failures:
+
+---- tests::fails stdout ----
+thread 'tests::fails' panicked at src/lib.rs:10:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::fails
+
+test result: FAILED. 1 passed; 1 failed; 2 ignored; 0 measured; 0 filtered out; finished in 0.25s
+
+running 1 test
+test src/lib.rs - add (line 3) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.50s
+";
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let report = CargoParser.parse(OUTPUT).unwrap();
+
+        assert_eq!((report.passed, report.failed, report.ignored), (2, 1, 2));
+        assert_eq!(report.duration_secs, Some(0.75));
+        assert_eq!(report.tests.len(), 5);
+        assert_eq!(report.tests[3].outcome, TestOutcome::Ignored);
+        assert_eq!(report.tests[4].name, "src/lib.rs - add (line 3)");
+
+        let failure = report.failures().next().unwrap();
+        assert_eq!(failure.name, "tests::fails");
+        let message = failure.message.as_deref().unwrap();
+        assert!(message.starts_with("thread 'tests::fails' panicked at src/lib.rs:10:9:"));
+        assert!(message.ends_with("to display a backtrace"));
+    }
+
+    #[test]
+    fn test_parse_report_time() {
+        let report = CargoParser.parse("test a ... ok <0.125s>\n").unwrap();
+        assert_eq!(report.tests[0].duration_secs, Some(0.125));
+        assert_eq!(report.passed, 1);
+    }
+
+    #[test]
+    fn test_not_libtest_output() {
+        assert!(CargoParser.parse("Compiling foo\nerror: could not compile\n").is_none());
+    }
+}
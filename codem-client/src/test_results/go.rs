@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+
+use super::TestParser;
+use crate::types::{TestCase, TestFormat, TestOutcome, TestReport};
+
+/// One line of `go test -json` (the `test2json` format)
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Event {
+    action: String,
+    #[serde(default)]
+    package: String,
+    test: Option<String>,
+    elapsed: Option<f64>,
+    output: Option<String>,
+}
+
+/// `go test -json` output. Test names are prefixed with their package.
+pub struct GoParser;
+
+impl TestParser for GoParser {
+    fn format(&self) -> TestFormat {
+        TestFormat::Go
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::new(TestFormat::Go);
+        let mut outputs: HashMap<String, String> = HashMap::new();
+        let mut duration = None;
+        let mut any_events = false;
+
+        // Build failures and other non-JSON lines can be mixed in
+        let events = output.lines().filter_map(|line| serde_json::from_str::<Event>(line).ok());
+        for event in events {
+            any_events = true;
+
+            let Some(test) = &event.test else {
+                // Package level results only carry the package's run time
+                if matches!(event.action.as_str(), "pass" | "fail") {
+                    if let Some(elapsed) = event.elapsed {
+                        duration = Some(duration.unwrap_or(0.0) + elapsed);
+                    }
+                }
+                continue;
+            };
+            let name = format!("{}.{}", event.package, test);
+
+            let outcome = match event.action.as_str() {
+                "output" => {
+                    if let Some(text) = &event.output {
+                        outputs.entry(name).or_default().push_str(text);
+                    }
+                    continue;
+                }
+                "pass" => TestOutcome::Passed,
+                "fail" => TestOutcome::Failed,
+                "skip" => TestOutcome::Ignored,
+                _ => continue,
+            };
+
+            let message = (outcome == TestOutcome::Failed)
+                .then(|| outputs.remove(&name))
+                .flatten()
+                .map(|text| text.trim().to_string());
+
+            report.tests.push(TestCase {
+                name,
+                outcome,
+                duration_secs: event.elapsed,
+                message,
+            });
+        }
+
+        if !any_events {
+            return None;
+        }
+
+        report.count_tests();
+        report.duration_secs = duration;
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"{"Action":"start","Package":"example.com/m"}
+{"Action":"run","Package":"example.com/m","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/m","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Action":"pass","Package":"example.com/m","Test":"TestAdd","Elapsed":0.01}
+{"Action":"run","Package":"example.com/m","Test":"TestDiv"}
+{"Action":"output","Package":"example.com/m","Test":"TestDiv","Output":"=== RUN   TestDiv\n"}
+{"Action":"output","Package":"example.com/m","Test":"TestDiv","Output":"    math_test.go:12: got 1, want 2\n"}
+{"Action":"output","Package":"example.com/m","Test":"TestDiv","Output":"--- FAIL: TestDiv (0.02s)\n"}
+{"Action":"fail","Package":"example.com/m","Test":"TestDiv","Elapsed":0.02}
+{"Action":"skip","Package":"example.com/m","Test":"TestSkip","Elapsed":0}
+{"Action":"fail","Package":"example.com/m","Elapsed":0.5}
+"#;
+
+    #[test]
+    fn test_parse_go_json() {
+        let report = GoParser.parse(OUTPUT).unwrap();
+
+        assert_eq!((report.passed, report.failed, report.ignored), (1, 1, 1));
+        assert_eq!(report.duration_secs, Some(0.5));
+
+        let failure = report.failures().next().unwrap();
+        assert_eq!(failure.name, "example.com/m.TestDiv");
+        assert_eq!(failure.duration_secs, Some(0.02));
+        assert_eq!(
+            failure.message.as_deref(),
+            Some("=== RUN   TestDiv\n    math_test.go:12: got 1, want 2\n--- FAIL: TestDiv (0.02s)")
+        );
+    }
+
+    #[test]
+    fn test_not_go_json() {
+        assert!(GoParser.parse("ok  \texample.com/m\t0.5s\n").is_none());
+    }
+}
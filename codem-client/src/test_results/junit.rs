@@ -0,0 +1,127 @@
+use super::{parse_seconds, TestParser};
+use crate::types::{TestCase, TestFormat, TestOutcome, TestReport};
+
+/// JUnit XML, as written by pytest `--junitxml`, nextest, Maven and most CI tooling.
+///
+/// The XML may be surrounded by other output; everything from the first
+/// `<testsuites` or `<testsuite` to the matching closing tag is parsed.
+pub struct JunitParser;
+
+fn extract_xml(output: &str) -> Option<&str> {
+    let (open, close) = ["testsuites", "testsuite"].into_iter().find_map(|tag| {
+        let open = output.find(&format!("<{}", tag))?;
+        let close_tag = format!("</{}>", tag);
+        let close = output.rfind(&close_tag)? + close_tag.len();
+        Some((open, close))
+    })?;
+
+    // Keep the XML declaration if there is one
+    let start = output[..open].rfind("<?xml").unwrap_or(open);
+    (start < close).then(|| &output[start..close])
+}
+
+impl TestParser for JunitParser {
+    fn format(&self) -> TestFormat {
+        TestFormat::Junit
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let document = roxmltree::Document::parse(extract_xml(output)?).ok()?;
+        let mut report = TestReport::new(TestFormat::Junit);
+
+        for case in document.descendants().filter(|n| n.has_tag_name("testcase")) {
+            let name = match case.attribute("classname").filter(|c| !c.is_empty()) {
+                Some(class) => format!("{}::{}", class, case.attribute("name").unwrap_or_default()),
+                None => case.attribute("name").unwrap_or_default().to_string(),
+            };
+
+            let problem = case
+                .children()
+                .find(|n| n.has_tag_name("failure") || n.has_tag_name("error"));
+            let skipped = case.children().any(|n| n.has_tag_name("skipped"));
+
+            let (outcome, message) = match problem {
+                Some(node) => {
+                    let text = node.text().unwrap_or_default().trim();
+                    let message = match node.attribute("message") {
+                        Some(message) if !text.is_empty() && !text.starts_with(message) => {
+                            format!("{}\n{}", message, text)
+                        }
+                        Some(message) if text.is_empty() => message.to_string(),
+                        _ => text.to_string(),
+                    };
+                    (TestOutcome::Failed, Some(message))
+                }
+                None if skipped => (TestOutcome::Ignored, None),
+                None => (TestOutcome::Passed, None),
+            };
+
+            report.tests.push(TestCase {
+                name,
+                outcome,
+                duration_secs: case.attribute("time").and_then(parse_seconds),
+                message,
+            });
+        }
+
+        report.count_tests();
+
+        // Total time of the top level suites
+        let root = document.root_element();
+        report.duration_secs = match root.attribute("time") {
+            Some(time) => parse_seconds(time),
+            None => root
+                .children()
+                .filter(|n| n.has_tag_name("testsuite"))
+                .filter_map(|n| n.attribute("time").and_then(parse_seconds))
+                .reduce(|a, b| a + b),
+        };
+
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"some build output
+<?xml version="1.0" encoding="utf-8"?>
+<testsuites>
+  <testsuite name="pytest" tests="4" failures="1" errors="1" skipped="1" time="0.25">
+    <testcase classname="tests.test_math" name="test_add" time="0.001"/>
+    <testcase classname="tests.test_math" name="test_div" time="0.002">
+      <failure message="ZeroDivisionError: division by zero">def test_div():
+&gt;       1 / 0</failure>
+    </testcase>
+    <testcase classname="tests.test_math" name="test_io" time="0.003">
+      <error message="fixture failed"/>
+    </testcase>
+    <testcase classname="tests.test_math" name="test_skip" time="0">
+      <skipped message="not yet"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+done
+"#;
+
+    #[test]
+    fn test_parse_junit_xml() {
+        let report = JunitParser.parse(OUTPUT).unwrap();
+
+        assert_eq!((report.passed, report.failed, report.ignored), (1, 2, 1));
+        assert_eq!(report.duration_secs, Some(0.25));
+        assert_eq!(report.tests[1].name, "tests.test_math::test_div");
+        assert_eq!(report.tests[1].duration_secs, Some(0.002));
+        assert_eq!(
+            report.tests[1].message.as_deref(),
+            Some("ZeroDivisionError: division by zero\ndef test_div():\n>       1 / 0")
+        );
+        assert_eq!(report.tests[2].message.as_deref(), Some("fixture failed"));
+    }
+
+    #[test]
+    fn test_not_junit_xml() {
+        assert!(JunitParser.parse("<html></html>").is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//! Parsers that turn test command output into a [`TestReport`].
+
+mod cargo;
+mod go;
+mod junit;
+mod nextest;
+mod pytest;
+
+pub use cargo::CargoParser;
+pub use go::GoParser;
+pub use junit::JunitParser;
+pub use nextest::NextestParser;
+pub use pytest::PytestParser;
+
+use crate::types::{TestFormat, TestReport};
+
+pub trait TestParser: Send + Sync {
+    fn format(&self) -> TestFormat;
+
+    /// Parse the combined output of a test command, or return `None` if it
+    /// isn't in this parser's format
+    fn parse(&self, output: &str) -> Option<TestReport>;
+}
+
+/// Every parser, in the order they are tried when the format isn't configured.
+/// More distinctive formats come first.
+const PARSERS: &[&dyn TestParser] = &[
+    &GoParser,
+    &JunitParser,
+    &NextestParser,
+    &CargoParser,
+    &PytestParser,
+];
+
+pub fn parser_for(format: TestFormat) -> &'static dyn TestParser {
+    match format {
+        TestFormat::Cargo => &CargoParser,
+        TestFormat::Nextest => &NextestParser,
+        TestFormat::Pytest => &PytestParser,
+        TestFormat::Junit => &JunitParser,
+        TestFormat::Go => &GoParser,
+    }
+}
+
+/// Parse test output with the parser for `format`, or with the first parser
+/// that recognises it if no format is given
+pub fn parse_test_output(format: Option<TestFormat>, output: &str) -> Option<TestReport> {
+    match format {
+        Some(format) => parser_for(format).parse(output),
+        None => PARSERS.iter().find_map(|parser| parser.parse(output)),
+    }
+}
+
+/// Parse a number of seconds like `0.52` or `0.52s`
+fn parse_seconds(text: &str) -> Option<f64> {
+    text.trim().trim_end_matches('s').parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TestOutcome;
+
+    #[test]
+    fn test_detects_format() {
+        let cargo = "running 1 test\ntest a ... ok\n\ntest result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s\n";
+        let go = r#"{"Action":"pass","Package":"p","Test":"TestA","Elapsed":0.01}"#;
+        let pytest = "tests/test_a.py::test_a PASSED [100%]\n===== 1 passed in 0.01s =====\n";
+
+        assert_eq!(parse_test_output(None, cargo).unwrap().format, TestFormat::Cargo);
+        assert_eq!(parse_test_output(None, go).unwrap().format, TestFormat::Go);
+        assert_eq!(parse_test_output(None, pytest).unwrap().format, TestFormat::Pytest);
+        assert!(parse_test_output(None, "hello world\n").is_none());
+    }
+
+    #[test]
+    fn test_configured_format_only_uses_that_parser() {
+        let pytest = "tests/test_a.py::test_a PASSED [100%]\n===== 1 passed in 0.01s =====\n";
+        assert!(parse_test_output(Some(TestFormat::Cargo), pytest).is_none());
+    }
+
+    #[test]
+    fn test_compact_summary() {
+        let output = "\
+running 2 tests
+test a ... ok
+test b ... FAILED
+
+failures:
+
+---- b stdout ----
+thread 'b' panicked at src/lib.rs:3:5:
+boom
+
+failures:
+    b
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.50s
+";
+        let report = parse_test_output(None, output).unwrap();
+        assert_eq!(report.tests[1].outcome, TestOutcome::Failed);
+        assert_eq!(
+            report.to_string(),
+            "cargo test: 1 passed, 1 failed, 0 ignored in 0.50s\nFAILED b\n    thread 'b' panicked at src/lib.rs:3:5:\n    boom"
+        );
+    }
+}
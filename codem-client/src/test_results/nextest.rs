@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use regex::Regex;
+
+use super::{parse_seconds, TestParser};
+use crate::types::{TestCase, TestFormat, TestOutcome, TestReport};
+
+/// `PASS [   0.003s] binary tests::name`, possibly after `TRY 2`
+static STATUS_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:TRY \d+ )?(PASS|FAIL|SKIP|LEAK|FLAKY|TIMEOUT|ABORT|SIG[A-Z]+)\s+\[\s*([\d.]+s)?\s*\]\s+(.+?)\s*$").unwrap()
+});
+
+static SUMMARY_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*Summary\s+\[\s*([\d.]+s)\s*\]").unwrap()
+});
+
+static SUMMARY_COUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d+) (passed|failed|skipped|timed out)").unwrap()
+});
+
+/// `--- STDERR: binary tests::name ---`, or the `──── STDERR:` style of newer versions
+static OUTPUT_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:-{3}|─+) STD(OUT|ERR):\s+(.+?)(?:\s+-{3})?\s*$").unwrap()
+});
+
+/// `cargo nextest run` output
+pub struct NextestParser;
+
+impl TestParser for NextestParser {
+    fn format(&self) -> TestFormat {
+        TestFormat::Nextest
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::new(TestFormat::Nextest);
+        let mut summary = false;
+
+        // Captured (stdout, stderr) of each test
+        let mut captured: HashMap<String, (String, String)> = HashMap::new();
+        let mut current: Option<(bool, String)> = None;
+
+        for line in output.lines() {
+            if let Some(caps) = SUMMARY_LINE.captures(line) {
+                summary = true;
+                current = None;
+                report.duration_secs = parse_seconds(&caps[1]);
+                for count in SUMMARY_COUNT.captures_iter(line) {
+                    let n = count[1].parse().unwrap_or(0);
+                    match &count[2] {
+                        "passed" => report.passed = n,
+                        "skipped" => report.ignored = n,
+                        _ => report.failed += n,
+                    }
+                }
+                continue;
+            }
+
+            if let Some(caps) = OUTPUT_HEADER.captures(line) {
+                current = Some((&caps[1] == "ERR", caps[2].to_string()));
+                continue;
+            }
+
+            if let Some(caps) = STATUS_LINE.captures(line) {
+                current = None;
+                // Failures are listed again after the summary
+                if summary {
+                    continue;
+                }
+                let outcome = match &caps[1] {
+                    "PASS" | "LEAK" | "FLAKY" => TestOutcome::Passed,
+                    "SKIP" => TestOutcome::Ignored,
+                    _ => TestOutcome::Failed,
+                };
+                let name = caps[3].to_string();
+                // A retried test is reported once per try; keep the last one
+                report.tests.retain(|t| t.name != name);
+                report.tests.push(TestCase {
+                    name,
+                    outcome,
+                    duration_secs: caps.get(2).and_then(|d| parse_seconds(d.as_str())),
+                    message: None,
+                });
+                continue;
+            }
+
+            if line.trim_start().starts_with("------------") || line.trim_start().starts_with("error: ") {
+                current = None;
+                continue;
+            }
+
+            if let Some((is_stderr, name)) = &current {
+                let entry = captured.entry(name.clone()).or_default();
+                let text = if *is_stderr { &mut entry.1 } else { &mut entry.0 };
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+
+        if report.tests.is_empty() && !summary {
+            return None;
+        }
+
+        for test in report.tests.iter_mut().filter(|t| t.outcome == TestOutcome::Failed) {
+            if let Some((stdout, stderr)) = captured.remove(&test.name) {
+                // The panic message is on stderr; stdout is mostly libtest's own output
+                let message = if stderr.trim().is_empty() { stdout } else { stderr };
+                test.message = Some(message.trim().to_string());
+            }
+        }
+
+        if !summary {
+            report.count_tests();
+        }
+
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+    Starting 3 tests across 1 binary (1 test skipped)
+        PASS [   0.003s] mycrate tests::a
+        FAIL [   0.004s] mycrate tests::b
+
+--- STDOUT:              mycrate tests::b ---
+
+running 1 test
+test tests::b ... FAILED
+
+--- STDERR:              mycrate tests::b ---
+thread 'tests::b' panicked at src/lib.rs:5:9:
+boom
+
+        PASS [   0.005s] mycrate tests::c
+------------
+     Summary [   0.012s] 3 tests run: 2 passed, 1 failed, 1 skipped
+        FAIL [   0.004s] mycrate tests::b
+error: test run failed
+";
+
+    #[test]
+    fn test_parse_nextest_output() {
+        let report = NextestParser.parse(OUTPUT).unwrap();
+
+        assert_eq!((report.passed, report.failed, report.ignored), (2, 1, 1));
+        assert_eq!(report.duration_secs, Some(0.012));
+        assert_eq!(report.tests.len(), 3);
+        assert_eq!(report.tests[1].name, "mycrate tests::b");
+        assert_eq!(report.tests[1].duration_secs, Some(0.004));
+        assert_eq!(
+            report.tests[1].message.as_deref(),
+            Some("thread 'tests::b' panicked at src/lib.rs:5:9:\nboom")
+        );
+    }
+
+    #[test]
+    fn test_not_nextest_output() {
+        assert!(NextestParser.parse("test a ... ok\n").is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::LazyLock;
+use regex::Regex;
+
+use super::{parse_seconds, TestParser};
+use crate::types::{TestCase, TestFormat, TestOutcome, TestReport};
+
+/// `tests/test_a.py::test_b PASSED [ 50%]`, printed with `-v`
+static VERBOSE_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\S+::.+?) (PASSED|FAILED|SKIPPED|ERROR|XFAIL|XPASS)\b").unwrap()
+});
+
+/// `FAILED tests/test_a.py::test_b - AssertionError`, printed with `-r`
+static SHORT_SUMMARY_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(PASSED|FAILED|ERROR|XFAIL|XPASS) (\S+::\S+)(?: - (.*))?$").unwrap()
+});
+
+/// `==== 1 failed, 2 passed in 0.12s ====`
+static FINAL_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^=*\s*((?:\d+ \w+(?:, )?)+) in ([\d.]+)s\b").unwrap()
+});
+
+static FINAL_COUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d+) (\w+)").unwrap());
+
+/// `____ test_b ____` starts the traceback of a failed test
+static FAILURE_HEADER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^_{3,} (.+?) _{3,}$").unwrap());
+
+static SECTION_HEADER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^={3,}").unwrap());
+
+/// pytest output. Per-test results need `-v` or `-rA`; otherwise only the
+/// counts and failure tracebacks are available.
+pub struct PytestParser;
+
+fn outcome(status: &str) -> TestOutcome {
+    match status {
+        "PASSED" | "XPASS" => TestOutcome::Passed,
+        "FAILED" | "ERROR" => TestOutcome::Failed,
+        _ => TestOutcome::Ignored,
+    }
+}
+
+impl TestParser for PytestParser {
+    fn format(&self) -> TestFormat {
+        TestFormat::Pytest
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::new(TestFormat::Pytest);
+        let mut counted = false;
+
+        let mut tracebacks: Vec<(String, Vec<&str>)> = Vec::new();
+        let mut in_traceback = false;
+
+        for line in output.lines() {
+            if let Some(caps) = FINAL_LINE.captures(line) {
+                counted = true;
+                report.duration_secs = parse_seconds(&caps[2]);
+                for count in FINAL_COUNT.captures_iter(&caps[1]) {
+                    let n: usize = count[1].parse().unwrap_or(0);
+                    match &count[2] {
+                        "passed" | "xpassed" => report.passed += n,
+                        "failed" | "error" | "errors" => report.failed += n,
+                        "skipped" | "xfailed" => report.ignored += n,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            if let Some(caps) = FAILURE_HEADER.captures(line) {
+                tracebacks.push((caps[1].to_string(), Vec::new()));
+                in_traceback = true;
+                continue;
+            }
+            if SECTION_HEADER.is_match(line) {
+                in_traceback = false;
+                continue;
+            }
+            if in_traceback {
+                if let Some((_, lines)) = tracebacks.last_mut() {
+                    lines.push(line);
+                }
+                continue;
+            }
+
+            if let Some(caps) = VERBOSE_LINE.captures(line) {
+                let name = caps[1].to_string();
+                if !report.tests.iter().any(|t| t.name == name) {
+                    report.tests.push(TestCase {
+                        name,
+                        outcome: outcome(&caps[2]),
+                        duration_secs: None,
+                        message: None,
+                    });
+                }
+                continue;
+            }
+
+            if let Some(caps) = SHORT_SUMMARY_LINE.captures(line) {
+                let name = caps[2].to_string();
+                let index = match report.tests.iter().position(|t| t.name == name) {
+                    Some(index) => index,
+                    None => {
+                        report.tests.push(TestCase {
+                            name,
+                            outcome: outcome(&caps[1]),
+                            duration_secs: None,
+                            message: None,
+                        });
+                        report.tests.len() - 1
+                    }
+                };
+                if let Some(message) = caps.get(3) {
+                    report.tests[index].message = Some(message.as_str().to_string());
+                }
+            }
+        }
+
+        if report.tests.is_empty() && !counted {
+            return None;
+        }
+
+        // Tracebacks are headed by the test's name without its file, with
+        // classes separated by dots
+        for (name, lines) in tracebacks {
+            let suffix = format!("::{}", name.replace('.', "::"));
+            let traceback = lines.join("\n").trim().to_string();
+            match report.tests.iter_mut().find(|t| t.name.ends_with(&suffix)) {
+                Some(test) => test.message = Some(traceback),
+                None => report.tests.push(TestCase {
+                    name,
+                    outcome: TestOutcome::Failed,
+                    duration_secs: None,
+                    message: Some(traceback),
+                }),
+            }
+        }
+
+        if !counted {
+            report.count_tests();
+        }
+
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+============================= test session starts ==============================
+collected 3 items
+
+tests/test_math.py::test_add PASSED                                      [ 33%]
+tests/test_math.py::TestDiv::test_zero FAILED                            [ 66%]
+tests/test_math.py::test_skip SKIPPED (no reason)                        [100%]
+
+=================================== FAILURES ===================================
+______________________________ TestDiv.test_zero _______________________________
+
+    def test_zero(self):
+>       assert 1 / 0
+E       ZeroDivisionError: division by zero
+
+tests/test_math.py:9: ZeroDivisionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::TestDiv::test_zero - ZeroDivisionError: division by zero
+==================== 1 failed, 1 passed, 1 skipped in 0.03s ====================
+";
+
+    #[test]
+    fn test_parse_verbose_output() {
+        let report = PytestParser.parse(OUTPUT).unwrap();
+
+        assert_eq!((report.passed, report.failed, report.ignored), (1, 1, 1));
+        assert_eq!(report.duration_secs, Some(0.03));
+        assert_eq!(report.tests.len(), 3);
+
+        let failure = report.failures().next().unwrap();
+        assert_eq!(failure.name, "tests/test_math.py::TestDiv::test_zero");
+        let message = failure.message.as_deref().unwrap();
+        assert!(message.starts_with("def test_zero(self):"));
+        assert!(message.ends_with("tests/test_math.py:9: ZeroDivisionError"));
+    }
+
+    #[test]
+    fn test_parse_quiet_output() {
+        let report = PytestParser.parse("..F\n1 failed, 2 passed in 0.10s\n").unwrap();
+        assert_eq!((report.passed, report.failed, report.ignored), (2, 1, 0));
+        assert!(report.tests.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+use crate::approval::ApprovalQueue;
+use crate::config::ClientConfig;
+use crate::error::ClientError;
+use crate::types::ApprovalStatus;
+use crate::{Client, Project};
+
+#[tokio::test]
+async fn test_risky_command_waits_for_approval() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    let session_file = temp_path.join("session").join("session.toml");
+
+    let mut project = Project::new(temp_path.to_path_buf());
+    project.approval.required = true;
+    project.approval.wait_ms = 50;
+
+    let config = ClientConfig::new(vec![project], session_file.clone(), vec![], vec![]).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.run_command_risky(&session_id, "touch approved", None, None, None, None).await;
+    assert!(matches!(result, Err(ClientError::ApprovalPending { id: 1 })), "{:?}", result);
+    assert!(!temp_path.join("approved").exists());
+
+    // Still pending
+    let result = client.await_approval(&session_id, 1, None).await;
+    assert!(matches!(result, Err(ClientError::ApprovalPending { id: 1 })), "{:?}", result);
+
+    // The reviewer decides through the queue on disk
+    let reviewer = ApprovalQueue::new(&session_file);
+    reviewer.decide(1, true, None).await.unwrap();
+    client.await_approval(&session_id, 1, None).await.unwrap();
+    assert!(temp_path.join("approved").exists());
+
+    // An approval is only good for one run
+    let result = client.await_approval(&session_id, 1, None).await;
+    assert!(matches!(result, Err(ClientError::ApprovalNotPending { status: ApprovalStatus::Ran, .. })), "{:?}", result);
+
+    client.run_command_risky(&session_id, "touch denied", None, None, None, None).await.unwrap_err();
+    reviewer.decide(2, false, Some("not now")).await.unwrap();
+    let result = client.await_approval(&session_id, 2, None).await;
+    assert!(matches!(result, Err(ClientError::CommandDenied { id: 2, reason: Some(ref r) }) if r == "not now"), "{:?}", result);
+    assert!(!temp_path.join("denied").exists());
+
+    let statuses: Vec<_> = client.list_approvals(&session_id).await.unwrap().iter().map(|r| r.status).collect();
+    assert_eq!(statuses, vec![ApprovalStatus::Ran, ApprovalStatus::Denied]);
+
+    // Requests belong to the session that made them
+    let other_session = client.create_session("test").await.unwrap();
+    let result = client.await_approval(&other_session, 2, None).await;
+    assert!(matches!(result, Err(ClientError::ApprovalNotFound { id: 2 })), "{:?}", result);
+}
+
+async fn approval_client(temp_path: &std::path::Path) -> (Client, String) {
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut project = Project::new(temp_path.to_path_buf());
+    project.approval.required = true;
+    project.approval.wait_ms = 50;
+
+    let config = ClientConfig::new(vec![project], temp_path.join("session").join("session.toml"), vec![], vec![]).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+    (client, session_id)
+}
+
+#[tokio::test]
+async fn test_risky_job_needs_approval() {
+    let temp = TempDir::new().unwrap();
+    let (client, session_id) = approval_client(temp.path()).await;
+
+    let result = client.start_job_risky(&session_id, "touch started", None).await;
+    assert!(matches!(result, Err(ClientError::JobNeedsApproval { .. })), "{:?}", result);
+    assert!(client.list_jobs(&session_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_approval_must_match_submitted_request() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    let (client, session_id) = approval_client(temp_path).await;
+    let request_file = |id: u64| temp_path.join("session").join("approvals").join(format!("{}.json", id));
+
+    // Only the owner can change the queue
+    client.run_command_risky(&session_id, "touch first", None, None, None, None).await.unwrap_err();
+    let mode = |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&temp_path.join("session").join("approvals")), 0o700);
+    assert_eq!(mode(&request_file(1)), 0o600);
+
+    // Command swapped before the reviewer saw it
+    client.run_command_risky(&session_id, "touch submitted", None, None, None, None).await.unwrap_err();
+    let contents = std::fs::read_to_string(request_file(2)).unwrap();
+    std::fs::write(request_file(2), contents.replace("touch submitted", "touch swapped")).unwrap();
+    ApprovalQueue::new(&temp_path.join("session").join("session.toml")).decide(2, true, None).await.unwrap();
+    let result = client.await_approval(&session_id, 2, None).await;
+    assert!(matches!(result, Err(ClientError::ApprovalInvalid { id: 2 })), "{:?}", result);
+    assert!(!temp_path.join("swapped").exists());
+}
+
+#[tokio::test]
+async fn test_approval_runs_once_when_awaited_concurrently() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    let (client, session_id) = approval_client(temp_path).await;
+
+    client.run_command_risky(&session_id, "echo ran >> runs", None, None, None, None).await.unwrap_err();
+    ApprovalQueue::new(&temp_path.join("session").join("session.toml")).decide(1, true, None).await.unwrap();
+
+    let (a, b, c) = tokio::join!(
+        client.await_approval(&session_id, 1, None),
+        client.await_approval(&session_id, 1, None),
+        client.await_approval(&session_id, 1, None),
+    );
+    let results = [a, b, c];
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1, "{:?}", results);
+    for result in results.iter().filter(|r| r.is_err()) {
+        assert!(matches!(result, Err(ClientError::ApprovalNotPending { status: ApprovalStatus::Ran, .. })), "{:?}", result);
+    }
+    assert_eq!(std::fs::read_to_string(temp_path.join("runs")).unwrap(), "ran\n");
+}
@@ -0,0 +1,117 @@
+use tempfile::TempDir;
+use codem_core::types::WriteResultDetails;
+use crate::config::ClientConfig;
+use crate::error::ClientError;
+use crate::types::{CheckStage, CheckStatus, RunKind};
+use crate::{Client, Project};
+
+fn stage(name: &str, command: &str) -> CheckStage {
+    CheckStage {
+        name: name.to_string(),
+        command: command.to_string(),
+        timeout_ms: None,
+    }
+}
+
+async fn create_client(dir: &TempDir, configure: impl FnOnce(&mut Project)) -> (Client, String) {
+    std::fs::create_dir_all(dir.path().join("session")).unwrap();
+
+    let mut project = Project::new(dir.path().to_path_buf());
+    project.checks = vec![
+        stage("format", "echo formatted"),
+        stage("lint", "echo 'warning: bad' >&2; exit 1"),
+        stage("test", "echo tested"),
+    ];
+    configure(&mut project);
+
+    let config = ClientConfig::new(
+        vec![project],
+        dir.path().join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+    (client, session_id)
+}
+
+#[tokio::test]
+async fn test_run_checks_reports_each_stage() {
+    let dir = TempDir::new().unwrap();
+    let (client, session_id) = create_client(&dir, |_| {}).await;
+
+    let report = client.run_checks(&session_id, None, None, None).await.unwrap();
+
+    let statuses: Vec<_> = report.results.iter().map(|r| r.status.clone()).collect();
+    assert_eq!(statuses, vec![
+        CheckStatus::Passed,
+        CheckStatus::Failed { exit_code: 1 },
+        CheckStatus::Passed,
+    ]);
+    assert_eq!(report.results[0].output, "formatted\n");
+    assert_eq!(report.results[1].output, "warning: bad\n");
+    assert!(!report.passed());
+}
+
+#[tokio::test]
+async fn test_run_checks_stops_on_failure() {
+    let dir = TempDir::new().unwrap();
+    let (client, session_id) = create_client(&dir, |p| p.stop_checks_on_failure = true).await;
+
+    let report = client.run_checks(&session_id, None, None, None).await.unwrap();
+    assert_eq!(report.results[2].status, CheckStatus::Skipped);
+
+    // The project setting can be overridden per run
+    let report = client.run_checks(&session_id, None, Some(false), None).await.unwrap();
+    assert_eq!(report.results[2].status, CheckStatus::Passed);
+}
+
+#[tokio::test]
+async fn test_run_selected_checks() {
+    let dir = TempDir::new().unwrap();
+    let (client, session_id) = create_client(&dir, |_| {}).await;
+
+    let stages = vec!["test".to_string(), "format".to_string()];
+    let report = client.run_checks(&session_id, Some(&stages), None, None).await.unwrap();
+    let names: Vec<_> = report.results.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["format", "test"]);
+    assert!(report.passed());
+
+    let stages = vec!["missing".to_string()];
+    let result = client.run_checks(&session_id, Some(&stages), None, None).await;
+    assert!(matches!(result, Err(ClientError::CheckStageNotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_checks_run_after_write() {
+    let dir = TempDir::new().unwrap();
+    let (client, session_id) = create_client(&dir, |p| p.checks_after_write = true).await;
+
+    let result = client.write_new_file(&session_id, std::path::Path::new("new.txt"), "hello\n", false).await.unwrap();
+
+    let WriteResultDetails::WithCheckOutput { output, passed, .. } = result.details else {
+        panic!("expected check output, got {:?}", result.details);
+    };
+    assert!(!passed);
+    assert!(output.starts_with("Checks: 2 passed, 1 failed"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_checks_after_write_are_recorded() {
+    let dir = TempDir::new().unwrap();
+    let (client, session_id) = create_client(&dir, |p| {
+        p.checks_after_write = true;
+        p.stop_checks_on_failure = true;
+    }).await;
+
+    client.write_new_file(&session_id, std::path::Path::new("new.txt"), "hello\n", false).await.unwrap();
+
+    let runs: Vec<_> = client.command_history(&session_id).await.unwrap()
+        .into_iter()
+        .map(|run| (run.kind, run.command, run.exit_code))
+        .collect();
+    assert_eq!(runs, vec![
+        (RunKind::Check, "echo formatted".to_string(), Some(0)),
+        (RunKind::Check, "echo 'warning: bad' >&2; exit 1".to_string(), Some(1)),
+    ]);
+}
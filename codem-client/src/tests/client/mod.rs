@@ -13,5 +13,5 @@ async fn test_create_and_get_session() {
     let client = create_test_client(dir.path(), None).await;
     let session_id = client.create_session("test").await.unwrap();
 
-    assert!(session_id.len() > 0);
+    assert!(!session_id.is_empty());
 }
\ No newline at end of file
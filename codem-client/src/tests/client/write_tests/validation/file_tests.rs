@@ -36,6 +36,7 @@ async fn test_write_nonexistent_file() {
 }
 
 #[tokio::test]
+#[allow(clippy::permissions_set_readonly_false)]
 async fn test_write_readable_not_writable_file() {
     let temp_dir = TempDir::new().unwrap();
     let client = create_test_client(temp_dir.path(), None).await;
@@ -1,6 +1,10 @@
 use std::fs;
+use std::path::PathBuf;
 use tempfile::TempDir;
+use codem_core::types::{GrepOptions, PathFilter};
+use crate::error::{ClientError, GrepError};
 use crate::tests::common::create_test_client;
+use crate::types::GrepPaging;
 
 #[tokio::test]
 async fn test_grep_file() {
@@ -16,8 +20,7 @@ async fn test_grep_file() {
         &session_id,
         &file_path,
         "test",
-        false,
-        0
+        &GrepOptions::default()
     ).await.unwrap();
 
     // Should get one GrepFileMatch with 2 matches in its matches vector
@@ -40,16 +43,14 @@ async fn test_grep_codebase() {
         &session_id,
         &file1_path,
         "test\\d+",
-        false,
-        0
+        &GrepOptions::default()
     ).await.unwrap();
 
     let file2_matches = client.grep_file(
         &session_id,
         &file2_path,
         "test\\d+",
-        false,
-        0
+        &GrepOptions::default()
     ).await.unwrap();
 
     // Should get one GrepFileMatch for each file, each with one match
@@ -70,13 +71,11 @@ async fn temp_test_grep_codebase() {
     let matches = client.grep_codebase(
         &session_id,
         None,
-        None,
         "write_file_partial",
-        false,
-        0
+        &GrepOptions::default()
     ).await.unwrap();
 
-    for m in &matches {
+    for m in &matches.files {
         // check if src/client/write/mod.rs is in the matches
         if m.path.ends_with("src/client/write/mod.rs") {
             found_file = true;
@@ -85,7 +84,7 @@ async fn temp_test_grep_codebase() {
 
     println!("{:#?}", matches);
 
-    assert_eq!(found_file, true);
+    assert!(found_file);
 }
 
 #[tokio::test]
@@ -101,10 +100,116 @@ async fn test_grep_empty_result() {
         &session_id,
         &file_path,
         "non-existent",
-        false,
-        0
+        &GrepOptions::default()
     ).await.unwrap();
 
     // Should get an empty vector of GrepFileMatch when no matches found
     assert_eq!(matches.len(), 0);
 }
+
+#[tokio::test]
+async fn test_grep_codebase_pages() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("session")).unwrap();
+    fs::write(dir.path().join("a.txt"), "hit\nhit\nhit\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "hit\n").unwrap();
+    fs::write(dir.path().join("c.txt"), "hit\nhit\nhit\nhit\nhit\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let mut paging = GrepPaging {
+        max_matches: Some(2),
+        max_matches_per_file: Some(4),
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    loop {
+        let page = client.grep_codebase_page(&session_id, None, "hit", &GrepOptions::default(), &paging).await.unwrap();
+        assert_eq!((page.total_files, page.total_matches), (3, 9));
+        pages.push(page.files.iter()
+            .map(|f| format!("{}:{}", f.path.display(), f.matches.len()))
+            .collect::<Vec<_>>());
+        let capped: Vec<_> = page.capped_files.iter().map(|c| (c.path.clone(), c.total_matches)).collect();
+        if pages.last().unwrap().iter().any(|f| f.starts_with("c.txt")) {
+            assert_eq!(capped, vec![(PathBuf::from("c.txt"), 5)]);
+        }
+        match page.next_cursor {
+            Some(cursor) => paging.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    // c.txt is capped at 4 of its 5 matches
+    assert_eq!(pages, vec![
+        vec!["a.txt:2"],
+        vec!["a.txt:1", "b.txt:1"],
+        vec!["c.txt:2"],
+        vec!["c.txt:2"],
+    ]);
+
+    paging.cursor = Some("nonsense".to_string());
+    let result = client.grep_codebase_page(&session_id, None, "hit", &GrepOptions::default(), &paging).await;
+    assert!(matches!(result, Err(ClientError::GrepError(GrepError::InvalidCursor { .. }))));
+
+    // A cursor only pages through the search it came from
+    paging.cursor = None;
+    let page = client.grep_codebase_page(&session_id, None, "hit", &GrepOptions::default(), &paging).await.unwrap();
+    paging.cursor = page.next_cursor;
+    let result = client.grep_codebase_page(&session_id, None, "hi", &GrepOptions::default(), &paging).await;
+    assert!(matches!(result, Err(ClientError::GrepError(GrepError::CursorMismatch { .. }))), "{:?}", result);
+    let options = GrepOptions { case_sensitive: true, ..Default::default() };
+    let result = client.grep_codebase_page(&session_id, None, "hit", &options, &paging).await;
+    assert!(matches!(result, Err(ClientError::GrepError(GrepError::CursorMismatch { .. }))), "{:?}", result);
+
+    // Page sizes may change between pages
+    paging.max_matches = Some(10);
+    client.grep_codebase_page(&session_id, None, "hit", &GrepOptions::default(), &paging).await.unwrap();
+
+    // Empty pages would never advance
+    for limits in [
+        GrepPaging { max_files: Some(0), ..Default::default() },
+        GrepPaging { max_matches: Some(0), ..Default::default() },
+        GrepPaging { max_matches_per_file: Some(0), ..Default::default() },
+    ] {
+        let result = client.grep_codebase_page(&session_id, None, "hit", &GrepOptions::default(), &limits).await;
+        assert!(matches!(result, Err(ClientError::GrepError(GrepError::InvalidLimit { .. }))), "{:?}", result);
+    }
+}
+
+#[tokio::test]
+async fn test_grep_codebase_path_filter() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src/a/tests")).unwrap();
+    fs::create_dir_all(dir.path().join("generated")).unwrap();
+    fs::write(dir.path().join("src/a/tests/x.rs"), "fn hit() {}").unwrap();
+    fs::write(dir.path().join("src/a/lib.rs"), "fn hit() {}").unwrap();
+    fs::write(dir.path().join("generated/g.rs"), "fn hit() {}").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let search = |path: Option<&'static str>, include: &[&str], exclude: &[&str]| {
+        let options = GrepOptions {
+            path_filter: Some(PathFilter {
+                include: include.iter().map(|g| g.to_string()).collect(),
+                exclude: exclude.iter().map(|g| g.to_string()).collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let client = &client;
+        let session_id = &session_id;
+        async move {
+            client.grep_codebase(session_id, path.map(std::path::Path::new), "hit", &options).await
+                .map(|results| results.files.into_iter().map(|f| f.path).collect::<Vec<_>>())
+        }
+    };
+
+    assert_eq!(search(Some("src"), &["src/**/tests/**"], &[]).await.unwrap(), vec![PathBuf::from("src/a/tests/x.rs")]);
+    assert_eq!(search(None, &[], &["generated/", "tests/"]).await.unwrap(), vec![PathBuf::from("src/a/lib.rs")]);
+    assert!(matches!(
+        search(None, &["{src"], &[]).await,
+        Err(ClientError::GrepError(GrepError::InvalidPattern(_)))
+    ));
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use crate::config::ClientConfig;
+use crate::project::{ImpactConfig, ImpactMapping};
+use crate::{Client, Project};
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A workspace where `app` depends on `lib`, and `tool` stands alone
+fn create_workspace(dir: &Path) {
+    write(&dir.join("Cargo.toml"), "[workspace]\nmembers = [\"lib\", \"app\", \"tool\"]\nresolver = \"2\"\n");
+    write(&dir.join("lib/Cargo.toml"), "[package]\nname = \"lib\"\nversion = \"0.1.0\"\nedition = \"2021\"\n");
+    write(&dir.join("lib/src/lib.rs"), "");
+    write(&dir.join("app/Cargo.toml"), "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nlib = { path = \"../lib\" }\n");
+    write(&dir.join("app/src/lib.rs"), "");
+    write(&dir.join("tool/Cargo.toml"), "[package]\nname = \"tool\"\nversion = \"0.1.0\"\nedition = \"2021\"\n");
+    write(&dir.join("tool/src/lib.rs"), "");
+}
+
+fn create_config(dir: &TempDir, impact: ImpactConfig) -> ClientConfig {
+    fs::create_dir_all(dir.path().join("session")).unwrap();
+
+    let mut project = Project::new(dir.path().to_path_buf());
+    project.test_command = Some("echo full suite".to_string());
+    project.impact = impact;
+
+    ClientConfig::new(
+        vec![project],
+        dir.path().join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap()
+}
+
+#[tokio::test]
+async fn test_select_affected_workspace_packages() {
+    let dir = TempDir::new().unwrap();
+    create_workspace(dir.path());
+    let client = Client::new(create_config(&dir, ImpactConfig {
+        cargo_workspace: true,
+        ..Default::default()
+    })).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let selection = client.select_affected_tests(&session_id).await.unwrap();
+    assert_eq!(selection.command, "echo full suite");
+    assert!(selection.fallback_reason.is_some());
+
+    client.write_new_file(&session_id, Path::new("lib/src/extra.rs"), "pub fn f() {}\n", false).await.unwrap();
+    let selection = client.select_affected_tests(&session_id).await.unwrap();
+    assert_eq!(selection.targets, vec!["-p app", "-p lib"]);
+    assert_eq!(selection.command, "cargo test -p app -p lib");
+
+    // Changing the workspace manifest affects everything
+    client.write_new_file(&session_id, Path::new("Cargo.lock"), "", false).await.unwrap();
+    let selection = client.select_affected_tests(&session_id).await.unwrap();
+    assert_eq!(selection.command, "echo full suite");
+    assert!(selection.fallback_reason.unwrap().contains("Cargo.lock"));
+}
+
+#[tokio::test]
+async fn test_written_files_persist_and_run_selection() {
+    let dir = TempDir::new().unwrap();
+    let impact = ImpactConfig {
+        command: Some("echo running {targets}".to_string()),
+        mappings: vec![ImpactMapping {
+            paths: vec!["docs/**".to_string()],
+            targets: vec!["doctests".to_string()],
+        }],
+        cargo_workspace: false,
+    };
+
+    let session_id = {
+        let client = Client::new(create_config(&dir, impact.clone())).await;
+        let session_id = client.create_session("test").await.unwrap();
+        client.write_new_file(&session_id, Path::new("docs/guide.md"), "# Guide\n", false).await.unwrap();
+        session_id
+    };
+
+    // Written files are stored with the session
+    let client = Client::new(create_config(&dir, impact)).await;
+    let selection = client.select_affected_tests(&session_id).await.unwrap();
+    assert_eq!(selection.command, "echo running doctests");
+
+    let run = client.run_test_selection(&session_id, &selection, false, None).await.unwrap();
+    assert_eq!(run.output.trim(), "running doctests");
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+use tempfile::TempDir;
+use codem_core::types::CommandOptions;
+use crate::error::ClientError;
+use crate::jobs::{JobLimits, JobManager};
+use crate::tests::common::create_test_client;
+use crate::types::JobStatus;
+
+async fn wait_for_exit(client: &crate::Client, session_id: &str, job_id: &str) -> JobStatus {
+    for _ in 0..100 {
+        let info = client.job_status(session_id, job_id).await.unwrap();
+        if info.status != JobStatus::Running {
+            return info.status;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Job {} did not finish", job_id);
+}
+
+#[tokio::test]
+async fn test_job_runs_in_background() {
+    let dir = TempDir::new().unwrap();
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let job = client.start_job_risky(&session_id, "echo first; sleep 0.2; echo second", None).await.unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+
+    let status = wait_for_exit(&client, &session_id, &job.id).await;
+    assert_eq!(status, JobStatus::Exited { exit_code: 0 });
+
+    // First tail returns everything, the next one only new output
+    let tail = client.tail_job(&session_id, &job.id, None).await.unwrap();
+    assert_eq!(tail.output, "first\nsecond\n");
+    assert_eq!(tail.offset, tail.output.len());
+
+    let tail = client.tail_job(&session_id, &job.id, None).await.unwrap();
+    assert_eq!(tail.output, "");
+
+    // An explicit offset rereads from that point
+    let tail = client.tail_job(&session_id, &job.id, Some(6)).await.unwrap();
+    assert_eq!(tail.output, "second\n");
+}
+
+#[tokio::test]
+async fn test_kill_job() {
+    let dir = TempDir::new().unwrap();
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let job = client.start_job_risky(&session_id, "exec sleep 30", None).await.unwrap();
+    let info = client.kill_job(&session_id, &job.id).await.unwrap();
+    assert_eq!(info.status, JobStatus::Killed);
+    assert!(info.finished_at.is_some());
+
+    let jobs = client.list_jobs(&session_id).await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].status, JobStatus::Killed);
+}
+
+#[tokio::test]
+async fn test_jobs_are_scoped_to_session() {
+    let dir = TempDir::new().unwrap();
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let other_session = client.create_session("test").await.unwrap();
+
+    let job = client.start_job_risky(&session_id, "echo hello", None).await.unwrap();
+
+    let result = client.job_status(&other_session, &job.id).await;
+    assert!(matches!(result, Err(ClientError::JobNotFound { .. })));
+    assert!(client.list_jobs(&other_session).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_start_job_checks_safety() {
+    let dir = TempDir::new().unwrap();
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.start_job(&session_id, "rm -rf something", None).await;
+    assert!(matches!(result, Err(ClientError::UnsafeCommand { .. })));
+
+    let job = client.start_job(&session_id, "echo hello", None).await.unwrap();
+    let status = wait_for_exit(&client, &session_id, &job.id).await;
+    assert_eq!(status, JobStatus::Exited { exit_code: 0 });
+}
+
+async fn wait_for_job(jobs: &JobManager, job_id: &str) {
+    for _ in 0..100 {
+        if jobs.status("s", job_id).unwrap().status != JobStatus::Running {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Job {} did not finish", job_id);
+}
+
+#[tokio::test]
+async fn test_job_output_is_capped() {
+    let jobs = JobManager::with_limits(JobLimits { buffer_bytes: 10, ..Default::default() });
+
+    let job = jobs.start("s", "printf 0123456789abcdefghij", CommandOptions::default());
+    wait_for_job(&jobs, &job.id).await;
+
+    // Only the newest output is kept, but offsets still count all of it
+    let tail = jobs.tail("s", &job.id, None).unwrap();
+    assert_eq!(tail.output, "abcdefghij");
+    assert_eq!((tail.offset, tail.skipped), (20, 10));
+    assert_eq!(jobs.status("s", &job.id).unwrap().output_len, 20);
+
+    let tail = jobs.tail("s", &job.id, Some(15)).unwrap();
+    assert_eq!((tail.output.as_str(), tail.skipped), ("fghij", 0));
+}
+
+#[tokio::test]
+async fn test_finished_jobs_are_evicted() {
+    let jobs = JobManager::with_limits(JobLimits { max_finished: 2, ..Default::default() });
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let job = jobs.start("s", "true", CommandOptions::default());
+        wait_for_job(&jobs, &job.id).await;
+        ids.push(job.id);
+    }
+    let running = jobs.start("s", "exec sleep 30", CommandOptions::default());
+
+    // The oldest finished job made way; running jobs are never evicted
+    let listed: Vec<_> = jobs.list("s").into_iter().map(|job| job.id).collect();
+    assert_eq!(listed, vec![ids[1].clone(), ids[2].clone(), running.id.clone()]);
+    assert!(matches!(jobs.status("s", &ids[0]), Err(ClientError::JobNotFound { .. })));
+    jobs.kill("s", &running.id).unwrap();
+
+    let expiring = JobManager::with_limits(JobLimits { finished_ttl: Duration::ZERO, ..Default::default() });
+    let job = expiring.start("s", "true", CommandOptions::default());
+    wait_for_job(&expiring, &job.id).await;
+    assert!(expiring.list("s").is_empty());
+}
@@ -28,6 +28,7 @@ async fn test_list_directory() {
             file_pattern: None,
             count_lines: true,
             recursive: false,
+            path_filter: None,
          }
     ).await.unwrap();
 
@@ -16,4 +16,4 @@ pub(crate) fn test_dir() -> TempDir {
 }
 
 mod basic;
-mod pattern_match;
\ No newline at end of file
+mod pattern_match;mod path_filter;
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use codem_core::types::{ListOptions, PathFilter, TreeEntry};
+use crate::error::ClientError;
+use crate::tests::common::create_test_client;
+
+fn files(tree: &TreeEntry) -> Vec<PathBuf> {
+    let mut files: Vec<_> = tree.children.iter()
+        .filter(|child| !child.entry.is_dir)
+        .map(|child| child.entry.path.clone())
+        .collect();
+    files.sort();
+    files
+}
+
+fn filtered(include: &[&str], exclude: &[&str]) -> ListOptions {
+    ListOptions {
+        recursive: true,
+        path_filter: Some(PathFilter {
+            include: include.iter().map(|g| g.to_string()).collect(),
+            exclude: exclude.iter().map(|g| g.to_string()).collect(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_list_with_path_filter() {
+    let test_dir = TempDir::new().unwrap();
+    fs::create_dir_all(test_dir.path().join("src/a/tests")).unwrap();
+    fs::create_dir_all(test_dir.path().join("generated")).unwrap();
+    fs::write(test_dir.path().join("src/a/tests/x.rs"), "").unwrap();
+    fs::write(test_dir.path().join("src/a/lib.rs"), "").unwrap();
+    fs::write(test_dir.path().join("generated/g.rs"), "").unwrap();
+    fs::write(test_dir.path().join("top.rs"), "").unwrap();
+
+    let client = create_test_client(test_dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    // Globs are relative to the project, even when listing a subdirectory
+    let tree = client.list_directory(&session_id, Some(std::path::Path::new("src")), filtered(&["src/**/tests/**"], &[]))
+        .await.unwrap();
+    assert_eq!(files(&tree), vec![PathBuf::from("a/tests/x.rs")]);
+
+    let tree = client.list_directory(&session_id, None, filtered(&["*.rs"], &["generated/", "session/"]))
+        .await.unwrap();
+    assert_eq!(files(&tree), vec![PathBuf::from("src/a/lib.rs"), PathBuf::from("src/a/tests/x.rs"), PathBuf::from("top.rs")]);
+
+    let result = client.list_directory(&session_id, None, filtered(&["src/[a"], &[])).await;
+    assert!(matches!(result, Err(ClientError::InvalidPathGlob(_))), "{:?}", result);
+}
@@ -24,6 +24,7 @@ async fn test_pattern_match() {
             file_pattern: Some("\\.txt$".to_string()), // Use proper regex pattern
             count_lines: true,
             recursive: false,
+            path_filter: None,
          }
     ).await.unwrap();
 
@@ -1,5 +1,10 @@
 pub(crate) mod list_directory_test;
 pub(crate) mod grep_test;
 mod run_command;
+mod jobs;
+mod approval;
+mod checks;
+mod impact;
+mod replace;
 pub(crate) mod client;
 mod common;
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use codem_core::types::GrepOptions;
+use crate::error::ClientError;
+use crate::tests::common::create_test_client;
+use crate::types::ReplaceHunk;
+
+#[tokio::test]
+async fn test_replace_codebase_preview_and_apply() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/a.rs"), "let old_name = 1;\nkeep();\nold_name + old_name\n").unwrap();
+    fs::write(dir.path().join("src/b.rs"), "nothing here\n").unwrap();
+    fs::write(dir.path().join("latin1.txt"), b"caf\xe9 old_name\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions { case_sensitive: true, ..Default::default() };
+
+    let preview = client.replace_codebase(&session_id, None, r"old_(\w+)", "new_$1", &options, false).await.unwrap();
+    assert!(!preview.applied);
+    assert_eq!(preview.replacements(), 3);
+    assert_eq!(preview.files.len(), 1);
+    assert_eq!(preview.files[0].path, PathBuf::from("src/a.rs"));
+    assert_eq!(preview.files[0].hunks, vec![
+        ReplaceHunk {
+            old_start: 1,
+            new_start: 1,
+            old_lines: vec!["let old_name = 1;".to_string()],
+            new_lines: vec!["let new_name = 1;".to_string()],
+        },
+        ReplaceHunk {
+            old_start: 3,
+            new_start: 3,
+            old_lines: vec!["old_name + old_name".to_string()],
+            new_lines: vec!["new_name + new_name".to_string()],
+        },
+    ]);
+    // Files that would need transcoding are left alone
+    assert_eq!(preview.errors.len(), 1);
+    assert_eq!(preview.errors[0].path, PathBuf::from("latin1.txt"));
+    assert!(fs::read_to_string(dir.path().join("src/a.rs")).unwrap().contains("old_name"));
+
+    let applied = client.replace_codebase(&session_id, None, r"old_(\w+)", "new_$1", &options, true).await.unwrap();
+    assert!(applied.applied);
+    assert_eq!(applied.files, preview.files);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/a.rs")).unwrap(),
+        "let new_name = 1;\nkeep();\nnew_name + new_name\n"
+    );
+
+    // The write was recorded, so the file can be edited again straight away
+    let again = client.replace_codebase(&session_id, None, "keep", "kept", &options, true).await.unwrap();
+    assert_eq!(again.replacements(), 1);
+}
+
+#[tokio::test]
+async fn test_replace_codebase_requires_preview() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("a.txt");
+    fs::write(&file, "one\ntwo\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions::default();
+
+    let result = client.replace_codebase(&session_id, None, "one", "1", &options, true).await;
+    assert!(matches!(result, Err(ClientError::ReplaceNotPreviewed { ref paths }) if paths == &[PathBuf::from("a.txt")]));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+
+    // A change made after the preview needs a new one
+    client.replace_codebase(&session_id, None, "one", "1", &options, false).await.unwrap();
+    fs::write(&file, "one\ntwo\nthree\n").unwrap();
+    fs::File::options().write(true).open(&file).unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+    let result = client.replace_codebase(&session_id, None, "one", "1", &options, true).await;
+    assert!(matches!(result, Err(ClientError::ReplaceNotPreviewed { .. })));
+
+    client.replace_codebase(&session_id, None, "one", "1", &options, false).await.unwrap();
+
+    // Previewing doesn't count as reading the file for other writes
+    let result = client.write_file_full(&session_id, Path::new("a.txt"), "other\n", false).await;
+    assert!(matches!(result, Err(ClientError::FileNotSynced { .. })), "{:?}", result);
+
+    client.replace_codebase(&session_id, None, "one", "1", &options, true).await.unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "1\ntwo\nthree\n");
+}
+
+#[tokio::test]
+async fn test_replace_codebase_multiline() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.rs"), "a\nfoo(\n    x)\nb\nfoo(y)\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions { multiline: true, ..Default::default() };
+
+    let preview = client.replace_codebase(&session_id, None, r"foo\(\s*(\w)\)", "bar($1)", &options, false).await.unwrap();
+    let hunks = &preview.files[0].hunks;
+    assert_eq!((hunks[0].old_start, hunks[0].new_start), (2, 2));
+    assert_eq!(hunks[0].old_lines, vec!["foo(", "    x)"]);
+    assert_eq!(hunks[0].new_lines, vec!["bar(x)"]);
+    // Later hunks account for the lines removed above them
+    assert_eq!((hunks[1].old_start, hunks[1].new_start), (5, 4));
+}
+
+#[tokio::test]
+async fn test_replace_codebase_crlf() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "foo\r\nbar foo\r\nfood\r\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions::default();
+
+    // `$` matches before the line ending in both the search and the replacement
+    let found = client.grep_codebase(&session_id, None, "foo$", &options).await.unwrap();
+    let lines: Vec<_> = found.files[0].matches.iter().flat_map(|m| m.match_lines.clone()).collect();
+    assert_eq!(lines, vec![1, 2]);
+
+    client.replace_codebase(&session_id, None, "foo$", "baz", &options, false).await.unwrap();
+    let applied = client.replace_codebase(&session_id, None, "foo$", "baz", &options, true).await.unwrap();
+    assert_eq!(applied.replacements(), 2);
+    assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "baz\r\nbar baz\r\nfood\r\n");
+
+    // The same goes for multiline searches
+    let options = GrepOptions { multiline: true, ..Default::default() };
+    let found = client.grep_codebase(&session_id, None, "baz$", &options).await.unwrap();
+    let lines: Vec<_> = found.files[0].matches.iter().flat_map(|m| m.match_lines.clone()).collect();
+    assert_eq!(lines, vec![1, 2]);
+    let preview = client.replace_codebase(&session_id, None, "baz$", "foo", &options, false).await.unwrap();
+    assert_eq!(preview.replacements(), 2);
+}
+
+#[tokio::test]
+async fn test_replace_codebase_reports_unwritable_files() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "one\n").unwrap();
+    let read_only = dir.path().join("b.txt");
+    let mut permissions = fs::metadata(&read_only).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&read_only, permissions).unwrap();
+    // Permissions don't stop root
+    if fs::OpenOptions::new().write(true).open(&read_only).is_ok() {
+        return;
+    }
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions::default();
+
+    client.replace_codebase(&session_id, None, "one", "1", &options, false).await.unwrap();
+    let applied = client.replace_codebase(&session_id, None, "one", "1", &options, true).await.unwrap();
+    assert!(applied.applied);
+    assert_eq!(applied.written, vec![PathBuf::from("a.txt")]);
+    assert_eq!(applied.errors.len(), 1);
+    assert_eq!(applied.errors[0].path, PathBuf::from("b.txt"));
+    assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "1\n");
+    assert_eq!(fs::read_to_string(&read_only).unwrap(), "one\n");
+}
+
+#[tokio::test]
+async fn test_replace_codebase_whole_word_matches_grep() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.sh"), "run -foo x-foo\ncall foo( 1) foo(2)\n").unwrap();
+
+    let client = create_test_client(dir.path(), None).await;
+    let session_id = client.create_session("test").await.unwrap();
+    let options = GrepOptions { whole_word: true, fixed_string: true, ..Default::default() };
+
+    // Patterns starting or ending with a non-word character
+    for (pattern, replacement, expected) in [
+        ("-foo", "-bar", "run -bar x-foo\ncall foo( 1) foo(2)\n"),
+        ("foo(", "bar(", "run -bar x-foo\ncall bar( 1) foo(2)\n"),
+    ] {
+        let found = client.grep_codebase(&session_id, None, pattern, &options).await.unwrap();
+        let grep_matches: usize = found.files.iter().flat_map(|f| &f.matches).map(|m| m.match_lines.len()).sum();
+        assert_eq!(grep_matches, 1, "{}", pattern);
+
+        let preview = client.replace_codebase(&session_id, None, pattern, replacement, &options, false).await.unwrap();
+        assert_eq!(preview.files.len(), 1, "{}", pattern);
+        client.replace_codebase(&session_id, None, pattern, replacement, &options, true).await.unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a.sh")).unwrap(), expected);
+    }
+}
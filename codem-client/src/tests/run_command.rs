@@ -1,6 +1,9 @@
+use std::path::Path;
 use crate::{Client, Project};
 use crate::config::ClientConfig;
 use crate::error::ClientError;
+use crate::types::RunKind;
+use codem_core::CommandError;
 use tempfile::TempDir;
 
 #[tokio::test]
@@ -31,25 +34,25 @@ async fn test_run_command() {
     let session_id = client.create_session("test").await.unwrap();
 
     // Safe command succeeds
-    let result = client.run_command(&session_id, "echo hello", Some(temp_path), None).await;
+    let result = client.run_command(&session_id, "echo hello", Some(temp_path), None, None, None).await;
     assert!(result.is_ok(), "Safe command failed: {:?}", result);
 
     // Safe command succeeds with run_command_risky
-    let result = client.run_command_risky(&session_id, "echo hello", Some(temp_path), None).await;
+    let result = client.run_command_risky(&session_id, "echo hello", Some(temp_path), None, None, None).await;
     assert!(result.is_ok(), "Safe command failed with run_command_risky: {:?}", result);
     
     // Risky command fails with run_command
-    let result = client.run_command(&session_id, "rm test.txt", Some(temp_path), None).await;
+    let result = client.run_command(&session_id, "rm test.txt", Some(temp_path), None, None, None).await;
     assert!(matches!(result, Err(ClientError::UnsafeCommand { .. })));
 
     // Risky command succeeds with run_command_risky
-    let result = client.run_command_risky(&session_id, "rm test.txt", Some(temp_path), None).await;
+    let result = client.run_command_risky(&session_id, "rm test.txt", Some(temp_path), None, None, None).await;
     assert!(result.is_ok(), "Risky command failed with run_command_risky: {:?}", result);
 
     // Test command returns output
-    let result = client.run_test_command(&session_id).await;
+    let result = client.run_test_command(&session_id, false, None).await;
     assert!(result.is_ok(), "Test command failed: {:?}", result);
-    assert_eq!(result.unwrap().trim(), "test output");
+    assert_eq!(result.unwrap().output.trim(), "test output");
 
     // No test command configured returns error
     let mut test_project2 = Project::new(temp_path.to_path_buf());
@@ -64,8 +67,439 @@ async fn test_run_command() {
     let client2 = Client::new(config2).await;
 
     let session_id2 = client2.create_session("test").await.unwrap();
-    let result = client2.run_test_command(&session_id2).await;
+    let result = client2.run_test_command(&session_id2, false, None).await;
     assert!(matches!(result, Err(ClientError::TestCommandNotConfigured)));
 
     // No need to clean up - TempDir handles that automatically
 }
+
+#[tokio::test]
+async fn test_run_command_uses_project_patterns() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.safe_patterns = vec![r"^pwd$".to_string()];
+    test_project.risky_patterns = vec![r"^echo danger.*".to_string()];
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![r"^echo .*".to_string()],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    // Global and project safe patterns both apply
+    let result = client.run_command(&session_id, "echo hello", None, None, None, None).await;
+    assert!(result.is_ok(), "Global safe command failed: {:?}", result);
+    let result = client.run_command(&session_id, "pwd", None, None, None, None).await;
+    assert!(result.is_ok(), "Project safe command failed: {:?}", result);
+
+    // Project risky pattern wins over the global safe pattern
+    let result = client.run_command(&session_id, "echo danger", None, None, None, None).await;
+    assert!(matches!(result, Err(ClientError::UnsafeCommand { .. })));
+}
+
+#[tokio::test]
+async fn test_run_command_risky_in_sandbox() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    let project_path = temp_path.join("project");
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::create_dir_all(&project_path).unwrap();
+
+    let mut test_project = Project::new(project_path.clone());
+    test_project.sandbox.enabled = true;
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    // Writing inside the project works
+    let result = client.run_command_risky(&session_id, "touch inside", None, None, None, None).await;
+    assert!(result.is_ok(), "Write inside project failed: {:?}", result);
+    assert!(project_path.join("inside").exists());
+
+    // Writing outside the project is denied
+    let result = client.run_command_risky(&session_id, "touch ../outside", None, None, None, None).await;
+    assert!(matches!(result, Err(ClientError::CommandError(_))));
+    assert!(!temp_path.join("outside").exists());
+}
+
+#[tokio::test]
+async fn test_run_command_redacts_project_secrets() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.env.set.insert("API_TOKEN".to_string(), "s3cr3t".to_string());
+    test_project.secrets = vec!["s3cr3t".to_string()];
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.run_command_risky(&session_id, "echo token=$API_TOKEN", None, None, None, None).await;
+    assert_eq!(result.unwrap().output, "token=[REDACTED]\n");
+
+    // Output in errors is redacted too
+    let result = client.run_command_risky(&session_id, "echo $API_TOKEN; exit 1", None, None, None, None).await;
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("[REDACTED]") && !err.contains("s3cr3t"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_run_test_command_parses_results() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.test_command = Some(
+        "printf 'test a ... ok\\ntest b ... FAILED\\n\\ntest result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.10s\\n'; exit 101"
+            .to_string(),
+    );
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.run_test_command(&session_id, false, None).await;
+    let Err(ClientError::TestCommandFailed { exit_code, report: Some(report), .. }) = result else {
+        panic!("expected a failed test run with results, got {:?}", result);
+    };
+    assert_eq!(exit_code, 101);
+    assert_eq!((report.passed, report.failed), (1, 1));
+    assert_eq!(report.failures().next().unwrap().name, "b");
+}
+
+#[tokio::test]
+async fn test_test_report_file_is_redacted() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.secrets = vec!["s3cr3t".to_string()];
+    test_project.test_report_path = Some("report.xml".into());
+    test_project.test_command = Some(
+        "printf '<testsuite><testcase name=\"t_s3cr3t\"><failure message=\"got s3cr3t\"/></testcase></testsuite>' > report.xml; exit 1"
+            .to_string(),
+    );
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.run_test_command(&session_id, false, None).await;
+    let Err(ClientError::TestCommandFailed { report: Some(report), .. }) = result else {
+        panic!("expected a failed test run with results, got {:?}", result);
+    };
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(!json.contains("s3cr3t"), "{}", json);
+    assert_eq!(report.tests[0].name, "t_[REDACTED]");
+}
+
+#[tokio::test]
+async fn test_commands_are_recorded_in_history() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.test_command = Some("echo tests ran; exit 3".to_string());
+    test_project.secrets = vec!["s3cr3t".to_string()];
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![r"^echo .*".to_string()],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    client.run_command(&session_id, "echo hello", None, None, None, None).await.unwrap();
+    client.run_command(&session_id, "rm -rf target", None, None, None, None).await.unwrap_err();
+    client.run_command_risky(&session_id, "echo s3cr3t; sleep 5", None, Some(100), None, None).await.unwrap_err();
+    client.run_test_command(&session_id, false, None).await.unwrap_err();
+
+    let runs = client.command_history(&session_id).await.unwrap();
+    let summary: Vec<_> = runs.iter()
+        .map(|r| (r.id, r.kind, r.command.as_str(), r.exit_code))
+        .collect();
+    assert_eq!(summary, vec![
+        (1, RunKind::Command, "echo hello", Some(0)),
+        (2, RunKind::Command, "rm -rf target", None),
+        (3, RunKind::CommandRisky, "echo [REDACTED]; sleep 5", None),
+        (4, RunKind::Test, "echo tests ran; exit 3", Some(3)),
+    ]);
+    assert_eq!(runs[0].cwd, temp_path);
+    assert!(runs[1].error.as_ref().unwrap().starts_with("Command is not marked as safe"), "{:?}", runs[1].error);
+    assert_eq!(runs[2].error.as_deref(), Some("timed out after 100ms"));
+    assert_eq!(runs[2].output, "[REDACTED]\n");
+
+    let run = client.command_run(&session_id, 4).await.unwrap();
+    assert_eq!(run.output, "tests ran\n");
+    assert!(matches!(client.command_run(&session_id, 5).await, Err(ClientError::CommandRunNotFound { id: 5 })));
+}
+
+#[tokio::test]
+async fn test_run_command_cwd_is_resolved_in_project() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::create_dir_all(temp_path.join("project/sub")).unwrap();
+
+    let config = ClientConfig::new(
+        vec![Project::new(temp_path.join("project"))],
+        temp_path.join("session").join("session.toml"),
+        vec![r"^pwd$".to_string()],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    // Relative to the project, not the server's working directory
+    let result = client.run_command(&session_id, "pwd", Some(Path::new("sub")), None, None, None).await;
+    assert_eq!(result.unwrap().output.trim(), temp_path.join("project/sub").to_str().unwrap());
+
+    // Rejected whether or not the directory exists
+    for cwd in ["..", "sub/../../session", "/tmp", "../../no/such/dir"] {
+        let result = client.run_command(&session_id, "pwd", Some(Path::new(cwd)), None, None, None).await;
+        assert!(matches!(result, Err(ClientError::PathOutOfScope { .. })), "{}: {:?}", cwd, result);
+        let result = client.start_job_risky(&session_id, "pwd", Some(Path::new(cwd))).await;
+        assert!(matches!(result, Err(ClientError::PathOutOfScope { .. })), "{}: {:?}", cwd, result);
+    }
+}
+
+#[tokio::test]
+async fn test_run_command_cwd_stays_in_session_project() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::create_dir_all(temp_path.join("project")).unwrap();
+    std::fs::create_dir_all(temp_path.join("other")).unwrap();
+    std::fs::create_dir_all(temp_path.join("outside")).unwrap();
+    std::os::unix::fs::symlink(temp_path.join("outside"), temp_path.join("project/link")).unwrap();
+
+    let mut other = Project::new(temp_path.join("other"));
+    other.name = "other".to_string();
+    let config = ClientConfig::new(
+        vec![Project::new(temp_path.join("project")), other],
+        temp_path.join("session").join("session.toml"),
+        vec![r"^pwd$".to_string()],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    // A sibling project configured on the same server, and a symlink out of the project
+    for cwd in ["../other", "link"] {
+        let result = client.run_command(&session_id, "pwd", Some(Path::new(cwd)), None, None, None).await;
+        assert!(matches!(result, Err(ClientError::PathOutOfScope { .. })), "{}: {:?}", cwd, result);
+        let result = client.start_job_risky(&session_id, "pwd", Some(Path::new(cwd))).await;
+        assert!(matches!(result, Err(ClientError::PathOutOfScope { .. })), "{}: {:?}", cwd, result);
+    }
+}
+
+#[tokio::test]
+async fn test_run_command_with_stdin() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+
+    let config = ClientConfig::new(
+        vec![Project::new(temp_path.to_path_buf())],
+        temp_path.join("session").join("session.toml"),
+        vec![r"^sort$".to_string()],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    let result = client.run_command(&session_id, "sort", None, None, Some("b\na\n"), None).await;
+    assert_eq!(result.unwrap().output, "a\nb\n");
+
+    let result = client.run_command_risky(&session_id, "read answer; echo got $answer", None, None, Some("yes\n"), None).await;
+    assert_eq!(result.unwrap().output, "got yes\n");
+}
+
+#[tokio::test]
+async fn test_run_test_command_reuses_result_until_files_change() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::write(temp_path.join(".gitignore"), ".env\n").unwrap();
+    std::fs::write(temp_path.join("lib.rs"), "").unwrap();
+    std::fs::write(temp_path.join(".env"), "A=1\n").unwrap();
+
+    // Counts how often the tests really run
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.test_command = Some("echo run >> runs; wc -l < runs".to_string());
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+    client.read_file(&session_id, Path::new("lib.rs")).await.unwrap();
+    client.read_file(&session_id, Path::new(".env")).await.unwrap();
+
+    let first = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert_eq!((first.output.trim(), first.cached_at), ("1", None));
+
+    let second = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert_eq!(second.output.trim(), "1");
+    assert!(second.cached_at.is_some());
+    assert!(second.summary().starts_with("[cached"), "{}", second.summary());
+
+    // Files the session has read are fingerprinted even if they're changed
+    // some other way, or are ignored
+    std::fs::write(temp_path.join("lib.rs"), "fn changed() {}").unwrap();
+    let third = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert_eq!((third.output.trim(), third.cached_at), ("2", None));
+    std::fs::write(temp_path.join(".env"), "A=changed\n").unwrap();
+    let env_changed = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert_eq!((env_changed.output.trim(), env_changed.cached_at), ("3", None));
+
+    // Bypassing the cache runs them again, and the new result is cached
+    let fourth = client.run_test_command(&session_id, true, None).await.unwrap();
+    assert_eq!((fourth.output.trim(), fourth.cached_at), ("4", None));
+    let fifth = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert_eq!(fifth.output.trim(), "4");
+    assert!(fifth.cached_at.is_some());
+
+    // Cached runs are in the history too
+    let cached: Vec<_> = client.command_history(&session_id).await.unwrap().iter().map(|r| r.cached_at.is_some()).collect();
+    assert_eq!(cached, vec![false, true, false, false, false, true]);
+
+}
+
+#[tokio::test]
+async fn test_tests_run_after_write_are_recorded_and_cached() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::write(temp_path.join(".gitignore"), "session/\n").unwrap();
+
+    let mut test_project = Project::new(temp_path.to_path_buf());
+    test_project.test_command = Some("echo tests ran".to_string());
+
+    let config = ClientConfig::new(
+        vec![test_project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+
+    client.write_new_file(&session_id, Path::new("new.txt"), "hello\n", true).await.unwrap();
+
+    let history = client.command_history(&session_id).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!((history[0].kind, history[0].command.as_str()), (RunKind::Test, "echo tests ran"));
+
+    // Nothing changed since, so the tests come from the cache
+    let run = client.run_test_command(&session_id, false, None).await.unwrap();
+    assert!(run.cached_at.is_some());
+}
+
+#[tokio::test]
+async fn test_run_command_reports_changed_files() {
+    for refresh in [false, true] {
+        let temp = TempDir::new().unwrap();
+        let temp_path = temp.path();
+        std::fs::create_dir_all(temp_path.join("session")).unwrap();
+        std::fs::write(temp_path.join("a.txt"), "a").unwrap();
+        std::fs::write(temp_path.join("b.txt"), "b").unwrap();
+
+        let mut project = Project::new(temp_path.to_path_buf());
+        project.refresh_changed_files = refresh;
+        let config = ClientConfig::new(
+            vec![project],
+            temp_path.join("session").join("session.toml"),
+            vec![],
+            vec![]
+        ).unwrap();
+        let client = Client::new(config).await;
+        let session_id = client.create_session("test").await.unwrap();
+        client.read_file(&session_id, &temp_path.join("a.txt")).await.unwrap();
+        client.read_file(&session_id, &temp_path.join("b.txt")).await.unwrap();
+
+        // Only files the session has read are reported
+        let result = client.run_command_risky(&session_id, "echo fmt > a.txt; echo new > c.txt", None, None, None, None).await.unwrap();
+        assert_eq!(result.changed_files, vec![Path::new("a.txt")]);
+        assert_eq!(result.timestamps_refreshed, refresh);
+
+        let write = client.write_file_full(&session_id, &temp_path.join("a.txt"), "edited", false).await;
+        if refresh {
+            write.unwrap();
+        } else {
+            assert!(matches!(write, Err(ClientError::FileModifiedSinceRead { .. })));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_failed_command_reports_changed_files() {
+    let temp = TempDir::new().unwrap();
+    let temp_path = temp.path();
+    std::fs::create_dir_all(temp_path.join("session")).unwrap();
+    std::fs::write(temp_path.join("a.txt"), "a").unwrap();
+
+    let mut project = Project::new(temp_path.to_path_buf());
+    project.refresh_changed_files = true;
+    let config = ClientConfig::new(
+        vec![project],
+        temp_path.join("session").join("session.toml"),
+        vec![],
+        vec![]
+    ).unwrap();
+    let client = Client::new(config).await;
+    let session_id = client.create_session("test").await.unwrap();
+    client.read_file(&session_id, &temp_path.join("a.txt")).await.unwrap();
+
+    let result = client.run_command_risky(&session_id, "echo fmt > a.txt; exit 1", None, None, None, None).await;
+    let Err(ClientError::CommandFailedWithChanges { error, changed_files, timestamps_refreshed }) = result else {
+        panic!("expected a failure with changed files, got {:?}", result);
+    };
+    assert!(matches!(error, CommandError::CommandFailed { exit_code: 1, .. }), "{:?}", error);
+    assert_eq!(changed_files, vec![Path::new("a.txt")]);
+    assert!(timestamps_refreshed);
+
+    // The refreshed timestamp still lets the file be written
+    client.write_file_full(&session_id, &temp_path.join("a.txt"), "edited", false).await.unwrap();
+
+    // Without changes, failures are reported as before
+    let result = client.run_command_risky(&session_id, "exit 1", None, None, None, None).await;
+    assert!(matches!(result, Err(ClientError::CommandError(CommandError::CommandFailed { .. }))), "{:?}", result);
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// Approved and already run; an approval is only good for one run
+    Ran,
+}
+
+impl std::fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApprovalStatus::Pending => write!(f, "pending"),
+            ApprovalStatus::Approved => write!(f, "approved"),
+            ApprovalStatus::Denied => write!(f, "denied"),
+            ApprovalStatus::Ran => write!(f, "ran"),
+        }
+    }
+}
+
+/// A risky command waiting for, or given, a human decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: u64,
+    pub session_id: String,
+    pub project: String,
+    pub command: String,
+    pub cwd: PathBuf,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// Seconds since the Unix epoch
+    pub requested_at: u64,
+    pub status: ApprovalStatus,
+    /// Given by the reviewer, usually when denying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
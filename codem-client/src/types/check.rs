@@ -0,0 +1,79 @@
+use std::fmt;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// A named step of a project's check pipeline, e.g. format, check, lint or test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckStage {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed { exit_code: i32 },
+    /// The command could not be run to completion, e.g. it timed out
+    Error { error: String },
+    /// Not run because an earlier stage failed
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Passed => write!(f, "passed"),
+            CheckStatus::Failed { exit_code } => write!(f, "failed with exit code {}", exit_code),
+            CheckStatus::Error { error } => write!(f, "error: {}", error),
+            CheckStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub command: String,
+    pub status: CheckStatus,
+    /// Combined stdout and stderr
+    pub output: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    /// Whether every stage that ran passed
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| matches!(r.status, CheckStatus::Passed | CheckStatus::Skipped))
+    }
+}
+
+/// One line per stage, each followed by its output
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |passed: bool| self.results.iter()
+            .filter(|r| r.status != CheckStatus::Skipped)
+            .filter(|r| (r.status == CheckStatus::Passed) == passed)
+            .count();
+        write!(f, "Checks: {} passed, {} failed", count(true), count(false))?;
+
+        for result in &self.results {
+            write!(f, "\n\n[{}] {}", result.status, result.name)?;
+            if result.status == CheckStatus::Skipped {
+                continue;
+            }
+            write!(f, ": {} ({:.2}s)", result.command, result.duration.as_secs_f64())?;
+            let output = result.output.trim_end();
+            if !output.is_empty() {
+                write!(f, "\n{}", output)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+/// Result of a command that exited successfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandResult {
+    /// Combined stdout and stderr
+    pub output: String,
+    /// Files read in the session that the command changed or removed,
+    /// relative to the project's base path
+    pub changed_files: Vec<PathBuf>,
+    /// Whether the session's timestamps for `changed_files` were refreshed,
+    /// so they can be written without being read again
+    pub timestamps_refreshed: bool,
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+use codem_core::types::{GrepFileError, GrepFileMatch};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -20,4 +22,57 @@ pub struct GrepResults {
     pub files_searched: usize,
     /// Total number of lines searched
     pub lines_searched: usize,
-}
\ No newline at end of file
+}
+
+/// Which page of a codebase search to return and how big it may be.
+/// Matches are counted as matching lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrepPaging {
+    /// Matches per page
+    pub max_matches: Option<usize>,
+    /// Files per page
+    pub max_files: Option<usize>,
+    /// Matches kept per file; the rest of a file's matches are dropped, not paged
+    pub max_matches_per_file: Option<usize>,
+    /// `next_cursor` of the previous page; `None` for the first page
+    pub cursor: Option<String>,
+}
+
+/// A file whose matches were cut short by `max_matches_per_file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CappedFile {
+    pub path: PathBuf,
+    /// Matches in the file before it was capped
+    pub total_matches: usize,
+}
+
+/// One page of codebase search results
+#[derive(Debug, Clone, Serialize)]
+pub struct GrepPage {
+    pub files: Vec<GrepFileMatch>,
+    /// Matching files across all pages
+    pub total_files: usize,
+    /// Matches across all pages, including ones dropped by the per-file cap
+    pub total_matches: usize,
+    /// Files on this page whose matches were capped
+    pub capped_files: Vec<CappedFile>,
+    /// Pass back to get the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Files that couldn't be searched; only given on the first page
+    pub errors: Vec<GrepFileError>,
+}
+
+impl GrepPage {
+    /// Matches on this page
+    pub fn match_count(&self) -> usize {
+        self.files.iter()
+            .flat_map(|file| &file.matches)
+            .map(|m| m.match_lines.len())
+            .sum()
+    }
+
+    /// Whether any results were left out of this page
+    pub fn truncated(&self) -> bool {
+        self.next_cursor.is_some() || !self.capped_files.is_empty()
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+
+/// The tool a command was run through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    Command,
+    CommandRisky,
+    Test,
+    Check,
+}
+
+impl std::fmt::Display for RunKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunKind::Command => write!(f, "run_command"),
+            RunKind::CommandRisky => write!(f, "run_command_risky"),
+            RunKind::Test => write!(f, "run_test_command"),
+            RunKind::Check => write!(f, "run_checks"),
+        }
+    }
+}
+
+/// A command run in a session, as recorded in its history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRun {
+    /// Position in the session's history, starting at 1
+    pub id: u64,
+    pub kind: RunKind,
+    pub command: String,
+    pub cwd: PathBuf,
+    pub started_at: SystemTime,
+    pub duration_ms: u64,
+    /// `None` if the command didn't exit on its own
+    pub exit_code: Option<i32>,
+    /// Why the command couldn't be run to completion, e.g. it was rejected or timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Combined stdout and stderr
+    pub output: String,
+    /// Whether the output was cut short before it was stored
+    #[serde(default)]
+    pub output_truncated: bool,
+    /// When the run this result was reused from was made, for test runs
+    /// answered from the cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<SystemTime>,
+}
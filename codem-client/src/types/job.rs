@@ -0,0 +1,43 @@
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Exited { exit_code: i32 },
+    Killed,
+    Failed { error: String },
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Exited { exit_code } => write!(f, "exited with code {}", exit_code),
+            JobStatus::Killed => write!(f, "killed"),
+            JobStatus::Failed { error } => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: String,
+    pub command: String,
+    pub status: JobStatus,
+    pub started_at: SystemTime,
+    pub finished_at: Option<SystemTime>,
+    /// Total bytes of output produced so far, including any no longer kept
+    pub output_len: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobOutput {
+    pub status: JobStatus,
+    /// Output produced since the previous tail of this job
+    pub output: String,
+    /// Byte offset into the job's output where the next tail will start
+    pub offset: usize,
+    /// Bytes after the requested offset that were discarded before they
+    /// could be returned
+    pub skipped: usize,
+}
@@ -1,2 +1,16 @@
+mod approval;
+mod check;
+mod command;
 mod grep;
-pub use grep::*;
\ No newline at end of file
+mod history;
+mod job;
+mod replace;
+mod test_report;
+pub use approval::*;
+pub use check::*;
+pub use command::*;
+pub use grep::*;
+pub use history::*;
+pub use job::*;
+pub use replace::*;
+pub use test_report::*;
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use codem_core::types::GrepFileError;
+use serde::Serialize;
+
+/// Consecutive lines changed by one or more replacements
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReplaceHunk {
+    /// 1-based line the hunk starts on before the edit
+    pub old_start: usize,
+    /// 1-based line the hunk starts on after the edits above it
+    pub new_start: usize,
+    pub old_lines: Vec<String>,
+    pub new_lines: Vec<String>,
+}
+
+/// The edits a codebase replacement makes to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReplacement {
+    /// Relative to the project root
+    pub path: PathBuf,
+    /// Matches whose replacement changes the text
+    pub replacements: usize,
+    pub hunks: Vec<ReplaceHunk>,
+}
+
+/// Result of previewing or applying a codebase replacement
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplaceResult {
+    pub files: Vec<FileReplacement>,
+    /// Files that couldn't be searched or written, or that aren't UTF-8 and
+    /// were left alone
+    pub errors: Vec<GrepFileError>,
+    /// Whether the edits were written, rather than only previewed
+    pub applied: bool,
+    /// Files the applied edits were written to
+    pub written: Vec<PathBuf>,
+}
+
+impl ReplaceResult {
+    /// Replacements across all files
+    pub fn replacements(&self) -> usize {
+        self.files.iter().map(|file| file.replacements).sum()
+    }
+}
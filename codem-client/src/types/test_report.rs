@@ -0,0 +1,182 @@
+use std::fmt;
+use std::time::SystemTime;
+use codem_core::redact::Redactor;
+use serde::{Deserialize, Serialize};
+
+/// Output formats test results can be parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFormat {
+    /// libtest output, as printed by `cargo test`
+    Cargo,
+    /// `cargo nextest run`
+    Nextest,
+    /// pytest, with `-v` or `-rA` for per-test results
+    Pytest,
+    /// JUnit XML, either printed or written to the project's `test_report_path`
+    Junit,
+    /// `go test -json`
+    Go,
+}
+
+impl fmt::Display for TestFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestFormat::Cargo => write!(f, "cargo test"),
+            TestFormat::Nextest => write!(f, "cargo nextest"),
+            TestFormat::Pytest => write!(f, "pytest"),
+            TestFormat::Junit => write!(f, "junit"),
+            TestFormat::Go => write!(f, "go test"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: TestOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    /// Failure message or captured output of a failed test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Test results parsed from the output of a test command
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestReport {
+    pub format: TestFormat,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    pub tests: Vec<TestCase>,
+}
+
+/// Lines of a failure message shown in the compact summary
+const SUMMARY_MESSAGE_LINES: usize = 10;
+
+impl TestReport {
+    pub fn new(format: TestFormat) -> Self {
+        Self {
+            format,
+            passed: 0,
+            failed: 0,
+            ignored: 0,
+            duration_secs: None,
+            tests: Vec::new(),
+        }
+    }
+
+    /// Redact secrets from test names and messages
+    pub fn redact(&mut self, redactor: &Redactor) {
+        for test in &mut self.tests {
+            test.name = redactor.redact(&test.name).into_owned();
+            if let Some(message) = &mut test.message {
+                *message = redactor.redact(message).into_owned();
+            }
+        }
+    }
+
+    /// Set the counts from the individual tests
+    pub fn count_tests(&mut self) {
+        let count = |outcome| self.tests.iter().filter(|t| t.outcome == outcome).count();
+        (self.passed, self.failed, self.ignored) =
+            (count(TestOutcome::Passed), count(TestOutcome::Failed), count(TestOutcome::Ignored));
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestCase> {
+        self.tests.iter().filter(|t| t.outcome == TestOutcome::Failed)
+    }
+}
+
+/// Compact summary: the counts, then each failed test with the start of its message
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} passed, {} failed, {} ignored", self.format, self.passed, self.failed, self.ignored)?;
+        if let Some(duration) = self.duration_secs {
+            write!(f, " in {:.2}s", duration)?;
+        }
+
+        for test in self.failures() {
+            write!(f, "\nFAILED {}", test.name)?;
+            if let Some(duration) = test.duration_secs {
+                write!(f, " ({:.2}s)", duration)?;
+            }
+            let Some(message) = &test.message else {
+                continue;
+            };
+            let lines: Vec<_> = message.trim_end().lines().collect();
+            for line in lines.iter().take(SUMMARY_MESSAGE_LINES) {
+                write!(f, "\n    {}", line)?;
+            }
+            if lines.len() > SUMMARY_MESSAGE_LINES {
+                write!(f, "\n    ... ({} more lines)", lines.len() - SUMMARY_MESSAGE_LINES)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The test command chosen for a run, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestSelection {
+    pub command: String,
+    /// Targets substituted into the impact command; empty for the full suite
+    pub targets: Vec<String>,
+    /// Why the full test suite is run instead of only the affected tests
+    pub fallback_reason: Option<String>,
+}
+
+impl fmt::Display for TestSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.fallback_reason {
+            Some(reason) => write!(f, "Running the full test suite ({})", reason),
+            None => write!(f, "Running affected tests: {}", self.command),
+        }
+    }
+}
+
+/// The result of running a project's test command
+#[derive(Debug, Clone)]
+pub struct TestRun {
+    /// Combined stdout and stderr
+    pub output: String,
+    pub exit_code: i32,
+    /// Parsed results, if the output was in a recognised format
+    pub report: Option<TestReport>,
+    /// When the run was made, if this is a cached result rather than a new run
+    pub cached_at: Option<SystemTime>,
+}
+
+impl TestRun {
+    /// The compact summary if results could be parsed, otherwise the raw output
+    pub fn summary(&self) -> String {
+        let summary = match &self.report {
+            Some(report) => report.to_string(),
+            None => self.output.clone(),
+        };
+        match self.cached_at {
+            Some(ran_at) => format!("{}\n{}", cached_note(ran_at), summary),
+            None => summary,
+        }
+    }
+}
+
+/// Marks a result returned from the cache instead of a new run
+pub fn cached_note(ran_at: SystemTime) -> String {
+    let age = SystemTime::now().duration_since(ran_at).unwrap_or_default();
+    format!(
+        "[cached: no files read or written in this session have changed since these tests ran {}s ago]",
+        age.as_secs()
+    )
+}
@@ -1,64 +1,447 @@
+use std::borrow::Cow;
+use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use wait_timeout::ChildExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
 
-use crate::types::CommandOutput;
+use crate::types::{
+    CommandEnv, CommandOptions, CommandOutput, OutputChunk, OutputSender, OutputStream, ResourceLimit, ResourceLimits,
+};
+use crate::redact::Redactor;
+use crate::sandbox;
 use crate::CommandError;
 
+/// `PATH` for commands started with a cleared environment
+const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// How long a process group gets to exit after SIGTERM before it is sent SIGKILL
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// How long to keep reading output after the command exits, in case
+/// background processes it started are still holding the pipes open
+const PIPE_GRACE: Duration = Duration::from_millis(500);
+
+/// How much of a pipe is read at a time
+const READ_CHUNK_BYTES: usize = 8 * 1024;
+
+/// Output without a newline is passed on once it reaches this length, so a
+/// command that never ends its line can't make us buffer all of it
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
 pub async fn run_command(
     command: &str,
     cwd: Option<&Path>,
     timeout_ms: Option<u64>,
+    stdin: Option<&str>,
+) -> Result<CommandOutput, CommandError> {
+    let options = CommandOptions {
+        cwd: cwd.map(Path::to_path_buf),
+        timeout_ms,
+        stdin: stdin.map(str::to_string),
+        ..Default::default()
+    };
+
+    run_command_with_options(command, &options).await
+}
+
+/// Run a command through `/bin/sh` in its own process group.
+///
+/// On timeout the whole group gets SIGTERM, then SIGKILL after a grace
+/// period. If the returned future is dropped before completion (e.g. the
+/// caller was cancelled), the group is terminated the same way.
+///
+/// If the command is killed for exceeding one of `options.limits`, this
+/// returns `CommandError::LimitExceeded`. That's only known when the signal
+/// kills the process we started, i.e. the shell or a command it `exec`s; a
+/// forked child killed by a limit shows up as the shell's exit code. With `options.sandbox` set, the
+/// command runs confined, or not at all if the sandbox can't be set up.
+pub async fn run_command_with_options(
+    command: &str,
+    options: &CommandOptions,
 ) -> Result<CommandOutput, CommandError> {
     let mut cmd = Command::new("/bin/sh");
     cmd.args(["-c", command]);
 
-
-    if let Some(cwd) = cwd {
+    if let Some(cwd) = &options.cwd {
         cmd.current_dir(cwd);
     }
 
+    // Never the server's own stdin, which may be carrying the protocol
+    cmd.stdin(if options.stdin.is_some() { Stdio::piped() } else { Stdio::null() });
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
+    cmd.process_group(0);
+    apply_env(&mut cmd, &options.env);
+
+    // Kept alive until the command is done; removed when dropped
+    let mut scratch_dir = None;
+    if let Some(sandbox) = &options.sandbox {
+        let dir = tempfile::Builder::new().prefix("codem-sandbox-").tempdir()?;
+        let prepared = sandbox::prepare(sandbox, dir.path())?;
+        cmd.env("TMPDIR", dir.path());
+        // SAFETY: entering the sandbox only makes raw syscalls
+        unsafe {
+            cmd.pre_exec(move || prepared.enter());
+        }
+        scratch_dir = Some(dir);
+    }
+
+    let limits = options.limits;
+    if limits.has_rlimits() {
+        // SAFETY: apply_rlimits only calls setrlimit, which is async-signal-safe
+        unsafe {
+            cmd.pre_exec(move || apply_rlimits(&limits));
+        }
+    }
 
-    eprintln!("running command: {:?}", cmd);
+    // Not the Command itself, whose Debug output includes env values
+    eprintln!("running command: {:?}", command);
     let mut child = cmd.spawn()?;
+    let mut group = ProcessGroup::new(&child);
 
-    let output = if let Some(timeout) = timeout_ms {
-        match child.wait_timeout(Duration::from_millis(timeout))? {
-            Some(status) => {
-                let output = child.wait_with_output()?;
-                CommandOutput {
-                    stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-                    exit_code: status.code().unwrap_or(-1),
-                }
+    // Written concurrently with reading the output, so a command that
+    // answers before consuming all its input can't deadlock us
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), options.stdin.clone()) {
+        tokio::spawn(async move {
+            // The command may exit without reading everything; that's not an error
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
+
+    // Read both pipes concurrently so neither can fill up and block the child
+    let max_bytes = limits.max_output_bytes;
+    let mut stdout = Reader::spawn(child.stdout.take(), OutputStream::Stdout, options, max_bytes);
+    let mut stderr = Reader::spawn(child.stderr.take(), OutputStream::Stderr, options, max_bytes);
+
+    let status = match options.timeout_ms {
+        Some(timeout) => match tokio::time::timeout(Duration::from_millis(timeout), child.wait()).await {
+            Ok(status) => Some(status?),
+            Err(_) => {
+                group.terminate(&mut child).await?;
+                None
             }
-            None => {
-                child.kill()?;
-                let output = child.wait_with_output()?;
-                let cmd_output = CommandOutput {
-                    stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-                    exit_code: -1,
-                };
-                return Err(CommandError::Timeout {
-                    timeout_ms: timeout,
-                    stdout: cmd_output.stdout.clone(),
-                    stderr: cmd_output.stderr.clone(),
-                    output: cmd_output,
-                });
+        },
+        None => Some(child.wait().await?),
+    };
+
+    // Anything left in the group now is a background process holding our pipes
+    if !(stdout.finish_within(PIPE_GRACE).await && stderr.finish_within(PIPE_GRACE).await) {
+        group.kill();
+    }
+    group.disarm();
+    drop(scratch_dir);
+
+    let mut limits_hit = Vec::new();
+    if stdout.truncated() || stderr.truncated() {
+        limits_hit.push(ResourceLimit::Output);
+    }
+
+    let output = CommandOutput {
+        stdout: stdout.into_string().await,
+        stderr: stderr.into_string().await,
+        exit_code: status.and_then(|s| s.code()).unwrap_or(-1),
+        limits_hit,
+    };
+
+    match (status, options.timeout_ms) {
+        (None, Some(timeout)) => Err(CommandError::Timeout {
+            timeout_ms: timeout,
+            stdout: output.stdout.clone(),
+            stderr: output.stderr.clone(),
+            output: Box::new(output),
+        }),
+        (Some(status), _) => match killed_by_limit(status, &limits) {
+            Some(limit) => Err(CommandError::LimitExceeded {
+                limit,
+                stdout: output.stdout.clone(),
+                stderr: output.stderr.clone(),
+                output: Box::new(output),
+            }),
+            None => Ok(output),
+        },
+        (None, None) => Ok(output),
+    }
+}
+
+fn apply_env(cmd: &mut Command, env: &CommandEnv) {
+    if env.clear {
+        cmd.env_clear();
+        for name in &env.pass {
+            if let Some(value) = std::env::var_os(name) {
+                cmd.env(name, value);
             }
         }
-    } else {
-        let output = child.wait_with_output()?;
-        CommandOutput {
-            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-            exit_code: output.status.code().unwrap_or(-1),
+        if !env.pass.iter().any(|name| name == "PATH") && !env.set.contains_key("PATH") {
+            cmd.env("PATH", DEFAULT_PATH);
         }
+    }
+    for name in &env.unset {
+        cmd.env_remove(name);
+    }
+    cmd.envs(&env.set);
+}
+
+fn apply_rlimits(limits: &ResourceLimits) -> std::io::Result<()> {
+    let set = |resource, value: Option<u64>, extra: u64| {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value.saturating_add(extra) as libc::rlim_t,
+        };
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
     };
 
-    Ok(output)
+    // The extra CPU second on the hard limit means SIGXCPU arrives before SIGKILL,
+    // so we can tell why the command died
+    set(libc::RLIMIT_CPU, limits.cpu_seconds, 1)?;
+    set(libc::RLIMIT_AS, limits.address_space_bytes, 0)?;
+    set(libc::RLIMIT_FSIZE, limits.file_size_bytes, 0)?;
+    set(libc::RLIMIT_NPROC, limits.max_processes, 0)?;
+    Ok(())
+}
+
+/// Work out whether the command was killed by one of its rlimits. Only a
+/// signal counts: an exit code of 128 + signal can't be told apart from a
+/// command that chose to exit with it.
+fn killed_by_limit(status: ExitStatus, limits: &ResourceLimits) -> Option<ResourceLimit> {
+    match status.signal()? {
+        libc::SIGXCPU if limits.cpu_seconds.is_some() => Some(ResourceLimit::CpuTime),
+        libc::SIGXFSZ if limits.file_size_bytes.is_some() => Some(ResourceLimit::FileSize),
+        _ => None,
+    }
+}
+
+/// The process group of a spawned command, terminated when dropped unless disarmed
+struct ProcessGroup {
+    pgid: Option<libc::pid_t>,
+}
+
+impl ProcessGroup {
+    fn new(child: &Child) -> Self {
+        // With process_group(0), the group id is the child's pid
+        Self {
+            pgid: child.id().map(|pid| pid as libc::pid_t),
+        }
+    }
+
+    fn signal(&self, signal: libc::c_int) {
+        if let Some(pgid) = self.pgid {
+            // Fails with ESRCH once every process in the group is gone, which is fine
+            unsafe {
+                libc::killpg(pgid, signal);
+            }
+        }
+    }
+
+    fn kill(&self) {
+        self.signal(libc::SIGKILL);
+    }
+
+    /// SIGTERM the group, give it time to exit, then SIGKILL whatever is left
+    async fn terminate(&self, child: &mut Child) -> std::io::Result<()> {
+        self.signal(libc::SIGTERM);
+        if tokio::time::timeout(KILL_GRACE, child.wait()).await.is_err() {
+            child.start_kill()?;
+        }
+        self.kill();
+        child.wait().await?;
+        Ok(())
+    }
+
+    fn disarm(&mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        let Some(pgid) = self.pgid else {
+            return;
+        };
+
+        self.signal(libc::SIGTERM);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    tokio::time::sleep(KILL_GRACE).await;
+                    unsafe {
+                        libc::killpg(pgid, libc::SIGKILL);
+                    }
+                });
+            }
+            Err(_) => self.kill(),
+        }
+    }
+}
+
+/// Collects one pipe of the child, forwarding each line as it arrives
+struct Reader {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    truncated: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Where a pipe's output goes once it's been split into lines
+struct LineSink {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    truncated: Arc<AtomicBool>,
+    stream: OutputStream,
+    sender: Option<OutputSender>,
+    redactor: Option<Arc<Redactor>>,
+    max_bytes: Option<usize>,
+}
+
+impl LineSink {
+    /// Redact, capture and forward one line, up to the output cap
+    fn line(&self, mut line: Vec<u8>) {
+        if let Some(redactor) = &self.redactor {
+            let text = String::from_utf8_lossy(&line);
+            if let Cow::Owned(redacted) = redactor.redact(&text) {
+                line = redacted.into_bytes();
+            }
+        }
+
+        let mut buffer = self.buffer.lock().unwrap();
+        if let Some(max_bytes) = self.max_bytes {
+            let remaining = max_bytes.saturating_sub(buffer.len());
+            if line.len() > remaining {
+                line.truncate(remaining);
+                self.truncated.store(true, Ordering::Relaxed);
+            }
+        }
+
+        if let Some(sender) = &self.sender {
+            // The receiver going away just means nobody is listening anymore
+            let _ = sender.send(OutputChunk {
+                stream: self.stream,
+                text: String::from_utf8_lossy(&line).into_owned(),
+            });
+        }
+        buffer.extend_from_slice(&line);
+    }
+
+    /// Where to cut a line too long to hold whole. Without a redactor it's
+    /// flushed as it is; with one, the end is held back for a secret that
+    /// may continue in the next read.
+    fn cut_point(&self, line: &[u8]) -> usize {
+        match &self.redactor {
+            Some(redactor) => redactor.cut_point(line),
+            None => line.len(),
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+}
+
+impl Reader {
+    /// Once `max_bytes` have been captured, further output is read but discarded
+    fn spawn<R>(
+        pipe: Option<R>,
+        stream: OutputStream,
+        options: &CommandOptions,
+        max_bytes: Option<usize>,
+    ) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let truncated = Arc::new(AtomicBool::new(false));
+        let sink = LineSink {
+            buffer: buffer.clone(),
+            truncated: truncated.clone(),
+            stream,
+            sender: options.output.clone(),
+            redactor: options.redactor.clone(),
+            max_bytes,
+        };
+
+        let handle = tokio::spawn(async move {
+            let Some(mut pipe) = pipe else {
+                return;
+            };
+
+            let mut chunk = vec![0; READ_CHUNK_BYTES];
+            let mut pending = Vec::new();
+            loop {
+                let read = match pipe.read(&mut chunk).await {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(err) => {
+                        eprintln!("error reading command {}: {}", stream, err);
+                        pending.extend_from_slice(format!("\n[error reading {}: {}]\n", stream, err).as_bytes());
+                        break;
+                    }
+                };
+                // Keep draining the pipe so the command isn't blocked, but
+                // hold on to nothing once the cap is reached
+                if sink.full() {
+                    continue;
+                }
+
+                pending.extend_from_slice(&chunk[..read]);
+                while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+                    let rest = pending.split_off(newline + 1);
+                    sink.line(std::mem::replace(&mut pending, rest));
+                }
+                if pending.len() >= MAX_LINE_BYTES {
+                    let cut = sink.cut_point(&pending);
+                    if cut > 0 {
+                        let rest = pending.split_off(cut);
+                        sink.line(std::mem::replace(&mut pending, rest));
+                    }
+                }
+            }
+            if !pending.is_empty() && !sink.full() {
+                sink.line(pending);
+            }
+        });
+
+        Self {
+            buffer,
+            truncated,
+            handle: Some(handle),
+        }
+    }
+
+    fn truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    /// Wait for the pipe to close, returning false if it is still open after `grace`
+    async fn finish_within(&mut self, grace: Duration) -> bool {
+        let Some(handle) = self.handle.as_mut() else {
+            return true;
+        };
+
+        if tokio::time::timeout(grace, handle).await.is_ok() {
+            self.handle = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    async fn into_string(mut self) -> String {
+        // Give the reader a moment to drain after the group was killed, then stop it
+        if !self.finish_within(PIPE_GRACE).await {
+            if let Some(handle) = self.handle.take() {
+                handle.abort();
+            }
+        }
+        let bytes = self.buffer.lock().unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
 }
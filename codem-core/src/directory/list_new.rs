@@ -32,6 +32,10 @@ pub async fn list_directory(
         .follow_links(false) // Never follow symlinks for safety
         .require_git(false); // Don't require being in a git repo
 
+    if let Some(filter) = options.path_filter.as_ref().filter(|f| !f.is_empty()) {
+        walker_builder.overrides(filter.overrides().map_err(DirectoryError::GlobError)?);
+    }
+
     // Set max depth if not recursive
     if !options.recursive {
         walker_builder.max_depth(Some(1));
@@ -67,7 +71,7 @@ pub async fn list_directory(
                 let is_symlink = metadata.file_type().is_symlink();
                 
                 // Process based on entry type
-                if entry.file_type().map_or(false, |ft| ft.is_dir()) {
+                if entry.file_type().is_some_and(|ft| ft.is_dir()) {
                     process_dir_entry(
                         base_path, 
                         &entry_path, 
@@ -77,7 +81,7 @@ pub async fn list_directory(
                         options,
                         &mut root
                     ).await?;
-                } else if entry.file_type().map_or(false, |ft| ft.is_file()) && matches {
+                } else if entry.file_type().is_some_and(|ft| ft.is_file()) && matches {
                     process_file_entry(
                         &entry_path,
                         relative_path,
@@ -1,6 +1,6 @@
 use error_set::error_set;
 
-use crate::types::CommandOutput;
+use crate::types::{CommandOutput, ResourceLimit};
 
 error_set! {
     DirectoryError = {
@@ -8,6 +8,8 @@ error_set! {
         RegexError(regex::Error),
         #[display("IO error: {0}")]
         IoError(std::io::Error),
+        #[display("Invalid path glob: {0}")]
+        GlobError(ignore::Error),
     };
 
     WriteError = {
@@ -75,7 +77,18 @@ error_set! {
             timeout_ms: u64,
             stdout: String,
             stderr: String,
-            output: CommandOutput,
+            output: Box<CommandOutput>,
+        },
+        #[display("Command was killed after exceeding its {limit}.\\nstdout:\\n{stdout}\\nstderr:\\n{stderr}")]
+        LimitExceeded {
+            limit: ResourceLimit,
+            stdout: String,
+            stderr: String,
+            output: Box<CommandOutput>,
+        },
+        #[display("Could not sandbox command: {reason}")]
+        SandboxUnavailable {
+            reason: String,
         },
         #[display("IO error when running command: {0}")]
         IoError(std::io::Error),
@@ -107,9 +107,9 @@ pub async fn process_large_partial_write(
     let remainder = &contents[remainder_start..];
     
     // Skip leading newline from remainder if our output has one or remainder has two
-    let remainder = if output.ends_with('\n') && remainder.starts_with('\n') {
-        &remainder[1..]
-    } else if remainder.starts_with("\n\n") {
+    let remainder = if (output.ends_with('\n') && remainder.starts_with('\n'))
+        || remainder.starts_with("\n\n")
+    {
         &remainder[1..]
     } else {
         remainder
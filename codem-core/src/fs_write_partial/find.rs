@@ -1,5 +1,5 @@
 use aho_corasick::AhoCorasick;
-use crate::types::{MatchInfo, PartialWrite, GrepMatch, LineRange};
+use crate::types::{MatchInfo, PartialWrite, GrepMatch, GrepSpan, LineRange};
 use crate::WriteError;
 use std::collections::HashSet;
 
@@ -26,7 +26,7 @@ pub fn is_within_line_range(line_number: usize, line_range: &Option<LineRange>)
     }
 }
 
-fn format_match_context(contents: &str, match_start: usize, _pattern: &str, context_lines: usize) -> GrepMatch {
+fn format_match_context(contents: &str, match_start: usize, pattern: &str, context_lines: usize) -> GrepMatch {
     let lines: Vec<&str> = contents.lines().collect();
     let match_line = get_line_number(contents, match_start);
     
@@ -40,9 +40,25 @@ fn format_match_context(contents: &str, match_start: usize, _pattern: &str, cont
         String::new()
     };
 
+    // The span stops at the end of the line if the pattern runs over it
+    let line_start = contents[..match_start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = contents[match_start..].find('\n').map_or(contents.len(), |i| match_start + i);
+    let text = &contents[match_start..line_end.min(match_start + pattern.len())];
+    let char_start = contents[line_start..match_start].chars().count();
+
     GrepMatch {
         line_number: match_line,
+        context_start: context_start + 1,
         context,
+        match_lines: vec![match_line],
+        spans: vec![GrepSpan {
+            line_number: match_line,
+            start: match_start - line_start,
+            end: match_start - line_start + text.len(),
+            char_start,
+            char_end: char_start + text.chars().count(),
+            text: text.to_string(),
+        }],
     }
 }
 
@@ -0,0 +1,48 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// How much of a file is looked at to decide whether it's binary
+pub(crate) const SNIFF_LEN: usize = 8 * 1024;
+
+/// Whether the start of a file looks binary. Text doesn't contain NUL bytes,
+/// unless it's UTF-16, which is recognised by its BOM or byte pattern.
+pub(crate) fn is_binary(head: &[u8]) -> bool {
+    Encoding::for_bom(head).is_none() && utf16_without_bom(head).is_none() && head.contains(&0)
+}
+
+/// UTF-16 without a BOM, going by mostly-ASCII text having a NUL in every
+/// other byte
+fn utf16_without_bom(head: &[u8]) -> Option<&'static Encoding> {
+    let pairs = head.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zeros = |offset: usize| head.chunks_exact(2).filter(|pair| pair[offset] == 0).count();
+    let (even, odd) = (zeros(0), zeros(1));
+    if even == 0 && odd * 2 > pairs {
+        Some(UTF_16LE)
+    } else if odd == 0 && even * 2 > pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Decode a text file. A BOM decides the encoding, then UTF-16 is tried by
+/// its byte pattern, then UTF-8; anything else is taken as Windows-1252,
+/// which covers Latin-1. Returns the encoding's name unless it was UTF-8.
+pub(crate) fn decode(bytes: &[u8]) -> (String, Option<String>) {
+    let encoding = match Encoding::for_bom(bytes) {
+        Some((encoding, _)) => encoding,
+        None => match utf16_without_bom(&bytes[..bytes.len().min(SNIFF_LEN)]) {
+            Some(encoding) => encoding,
+            None => match std::str::from_utf8(bytes) {
+                Ok(text) => return (text.to_string(), None),
+                Err(_) => WINDOWS_1252,
+            },
+        },
+    };
+    // decode() strips the BOM, if there is one
+    let (text, encoding, _) = encoding.decode(bytes);
+    let name = (encoding != UTF_8).then(|| encoding.name().to_string());
+    (text.into_owned(), name)
+}
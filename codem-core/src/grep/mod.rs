@@ -1,5 +1,18 @@
+mod decode;
 mod processor;
 mod search;
 
+use regex::Regex;
+use crate::types::GrepOptions;
+
 pub use processor::grep_file;
-pub use search::grep_codebase;
\ No newline at end of file
+pub use search::grep_codebase;
+
+/// Compile a search pattern, escaping it first if it's a fixed string
+pub fn build_pattern(pattern: &str, options: &GrepOptions) -> Result<Regex, regex::Error> {
+    if options.fixed_string {
+        Regex::new(&regex::escape(pattern))
+    } else {
+        Regex::new(pattern)
+    }
+}
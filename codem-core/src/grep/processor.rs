@@ -1,30 +1,72 @@
 use std::path::Path;
-use grep::regex::RegexMatcherBuilder;
-use grep::searcher::{Searcher, SearcherBuilder, Sink, SinkMatch};
+use grep::matcher::Matcher;
+use grep::regex::{RegexMatcher, RegexMatcherBuilder};
+use grep::matcher::LineTerminator;
+use grep::searcher::{sinks, BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch};
 use tokio::fs;
-use tokio::io;
+use tokio::io::{self, AsyncReadExt};
 use std::sync::{Arc, Mutex};
 use regex::Regex;
 
-use crate::types::{GrepMatch, GrepFileMatch, GrepOptions};
+use crate::types::{GrepMatch, GrepFileMatch, GrepOptions, GrepSpan};
+use super::decode::{self, SNIFF_LEN};
 
 struct GrepSink {
     matches: Arc<Mutex<Vec<GrepMatch>>>,
     lines: Arc<Vec<String>>,
     context_lines: usize,
+    matcher: RegexMatcher,
+    /// End (exclusive, 0-based) of the last match's context
+    last_context_end: usize,
 }
 
 impl GrepSink {
-    fn new(content: String, context_lines: usize) -> Self {
+    fn new(content: String, context_lines: usize, matcher: RegexMatcher) -> Self {
         let lines: Vec<String> = content.lines().map(String::from).collect();
         Self {
             matches: Arc::new(Mutex::new(Vec::new())),
             lines: Arc::new(lines),
             context_lines,
+            matcher,
+            last_context_end: 0,
         }
     }
 }
 
+/// Find every hit of the matcher within `text`, which starts at the
+/// beginning of line `first_line` and may span several lines
+fn find_spans(matcher: &RegexMatcher, first_line: usize, text: &str) -> io::Result<Vec<GrepSpan>> {
+    let mut spans = Vec::new();
+    // How far `text` has been scanned: the line reached, where it starts,
+    // and the character column within it
+    let (mut line_number, mut line_start, mut pos, mut column) = (first_line, 0, 0, 0);
+    matcher.find_iter(text.as_bytes(), |m| {
+        // Hits come in order, so each character is only counted once
+        for (i, c) in text[pos..m.start()].char_indices() {
+            if c == '\n' {
+                line_number += 1;
+                line_start = pos + i + 1;
+                column = 0;
+            } else {
+                column += 1;
+            }
+        }
+        let hit = &text[m.start()..m.end()];
+        let char_start = column;
+        spans.push(GrepSpan {
+            line_number,
+            start: m.start() - line_start,
+            end: m.end() - line_start,
+            char_start,
+            char_end: char_start + hit.chars().count(),
+            text: hit.to_string(),
+        });
+        pos = m.start();
+        true
+    }).map_err(io::Error::other)?;
+    Ok(spans)
+}
+
 impl Sink for GrepSink {
     type Error = io::Error;
 
@@ -36,41 +78,108 @@ impl Sink for GrepSink {
             None => return Ok(true), // Skip invalid lines
         };
         
+        // A multiline match can cover several lines
+        let text = std::str::from_utf8(mat.bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let last_line = line_num + mat.lines().count().max(1) - 1;
+
         let context_start = (line_num - 1).saturating_sub(self.context_lines);
-        let context_end = usize::min(line_num - 1 + self.context_lines + 1, lines.len());
+        let context_end = usize::min(last_line + self.context_lines, lines.len());
         
-        let context = lines[context_start..context_end].join("\n");
+        let spans = find_spans(&self.matcher, line_num, text)?;
 
         let mut matches = self.matches.lock().unwrap();
+
+        // Windows that overlap or touch the previous one extend it, so
+        // nearby matches don't repeat the same lines
+        if let Some(last) = matches.last_mut().filter(|_| self.context_lines > 0 && context_start <= self.last_context_end) {
+            if context_end > self.last_context_end {
+                last.context.push('\n');
+                last.context.push_str(&lines[self.last_context_end..context_end].join("\n"));
+                self.last_context_end = context_end;
+            }
+            // Multiline matches may share a line with the previous one
+            let first_new = line_num.max(last.match_lines.last().map_or(0, |line| line + 1));
+            last.match_lines.extend(first_new..=last_line);
+            last.spans.extend(spans);
+            return Ok(true);
+        }
+
         matches.push(GrepMatch {
             line_number: line_num,
-            context,
+            context_start: context_start + 1,
+            context: lines[context_start..context_end].join("\n"),
+            match_lines: (line_num..=last_line).collect(),
+            spans,
         });
+        self.last_context_end = context_end;
         
         Ok(true)
     }
 }
 
+/// Whether a binary file contains the pattern anywhere, stopping at the first hit
+fn binary_file_matches(path: &Path, matcher: &RegexMatcher) -> io::Result<bool> {
+    let mut found = false;
+    SearcherBuilder::new()
+        .binary_detection(BinaryDetection::none())
+        .line_terminator(LineTerminator::crlf())
+        .build()
+        .search_path(matcher, path, sinks::Bytes(|_, _| {
+            found = true;
+            Ok(false)
+        }))?;
+    Ok(found)
+}
+
 pub async fn grep_file(path: impl AsRef<Path>, pattern: &Regex, options: &GrepOptions) -> io::Result<Option<GrepFileMatch>> {
-    let content = fs::read_to_string(path.as_ref()).await?;
+    let mut file = fs::File::open(path.as_ref()).await?;
+    let mut bytes = Vec::new();
+    (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut bytes).await?;
+    let binary = decode::is_binary(&bytes);
+    if binary && !options.search_binary {
+        return Ok(None);
+    }
+
+    let mut builder = RegexMatcherBuilder::new();
+    builder.case_insensitive(!options.case_sensitive);
+    builder.word(options.whole_word);
+    builder.multi_line(options.multiline);
+    // `$` matches before `\r\n` too, which the searcher has to agree on
+    builder.crlf(true);
+    if options.multiline {
+        // Patterns may match line breaks, so none are ruled out
+        builder.line_terminator(None);
+    }
+    let matcher = builder.build(pattern.as_str())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    if binary {
+        // Binary files are streamed rather than read in, and only reported
+        return Ok(binary_file_matches(path.as_ref(), &matcher)?.then(|| GrepFileMatch {
+            path: path.as_ref().to_path_buf(),
+            matches: Vec::new(),
+            binary: true,
+            encoding: None,
+        }));
+    }
+
+    file.read_to_end(&mut bytes).await?;
+    let (content, encoding) = decode::decode(&bytes);
 
     let mut searcher = SearcherBuilder::new()
+        .line_terminator(LineTerminator::crlf())
         .line_number(true)
+        .multi_line(options.multiline)
         .build();
 
-    let sink = GrepSink::new(content.clone(), options.context_lines);
+    let sink = GrepSink::new(content.clone(), options.context_lines, matcher.clone());
     let matches = Arc::clone(&sink.matches);
 
-    let mut builder = RegexMatcherBuilder::new();
-builder.case_insensitive(!options.case_sensitive);
-let matcher = builder.build(pattern.as_str())
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-
     #[cfg(test)]
     eprintln!("Searching {} with pattern {:?}", path.as_ref().display(), pattern);    
 
     searcher.search_reader(&matcher, content.as_bytes(), sink)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
         
     #[cfg(test)]
     eprintln!("Found {} matches", matches.lock().unwrap().len());
@@ -83,6 +192,8 @@ let matcher = builder.build(pattern.as_str())
         Some(GrepFileMatch {
             path: path.as_ref().to_path_buf(),
             matches: matches.to_vec(),
+            binary: false,
+            encoding,
         })
     })
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use futures::stream::{FuturesUnordered, StreamExt};
 use ignore::WalkBuilder;
 use tokio::io;
@@ -6,9 +6,28 @@ use regex::Regex;
 use regex::RegexBuilder;
 use std::sync::Arc;
 
-use crate::types::{GrepFileMatch, GrepOptions};
+use crate::types::{GrepCodebaseResult, GrepFileError, GrepFileMatch, GrepOptions};
 use super::processor::grep_file;
 
+/// The path a walk error is about, if it says
+fn walk_error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => walk_error_path(err),
+        _ => None,
+    }
+}
+
+/// Add one file's outcome to the results; files that fail are reported
+/// alongside the matches
+fn record(results: &mut GrepCodebaseResult, path: PathBuf, result: io::Result<Option<GrepFileMatch>>) {
+    match result {
+        Ok(Some(file_match)) => results.files.push(file_match),
+        Ok(None) => {}
+        Err(err) => results.errors.push(GrepFileError { path, message: err.to_string() }),
+    }
+}
+
 struct SearchContext {
     options: GrepOptions,
     pattern: Regex,
@@ -18,8 +37,8 @@ pub async fn grep_codebase(
     root: impl AsRef<Path>, 
     pattern: &Regex,
     options: &GrepOptions,
-) -> io::Result<Vec<GrepFileMatch>> {
-    let mut matches = Vec::new();
+) -> io::Result<GrepCodebaseResult> {
+    let mut results = GrepCodebaseResult::default();
     let max_concurrent = num_cpus::get();
     
     let mut walker_builder = WalkBuilder::new(root.as_ref());
@@ -29,6 +48,12 @@ pub async fn grep_codebase(
         .git_ignore(true) // Use .gitignore files
         .follow_links(false) // Never follow symlinks for safety
         .require_git(false); // Don't require being in a git repo
+
+    if let Some(filter) = options.path_filter.as_ref().filter(|f| !f.is_empty()) {
+        let overrides = filter.overrides()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        walker_builder.overrides(overrides);
+    }
         
     #[cfg(test)]
     {
@@ -57,7 +82,7 @@ pub async fn grep_codebase(
                 #[cfg(test)]
                 {
                     eprintln!("Entry path: {:?}", entry.path());
-                    if entry.file_type().map_or(false, |ft| ft.is_file()) {
+                    if entry.file_type().is_some_and(|ft| ft.is_file()) {
                         if let Ok(content) = std::fs::read_to_string(entry.path()) {
                             eprintln!("Content: {}", content);
                         }
@@ -65,7 +90,7 @@ pub async fn grep_codebase(
                 }
                 
                 // Skip directories
-                if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                if !entry.file_type().is_some_and(|ft| ft.is_file()) {
                     continue;
                 }
 
@@ -75,34 +100,34 @@ pub async fn grep_codebase(
                 // Apply file pattern filter if specified
                 if let Some(pat) = &context.options.file_pattern {
                     let file_name = path.file_name().map(|s| s.to_string_lossy()).unwrap_or_default();
-                    if !glob::Pattern::new(pat).map_or(false, |p| p.matches(&file_name)) {
+                    if !glob::Pattern::new(pat).is_ok_and(|p| p.matches(&file_name)) {
                         continue;
                     }
                 }
 
                 if futures.len() >= max_concurrent {
-                    if let Some(Ok(Some(result))) = futures.next().await {
-                        matches.push(result);
+                    if let Some((path, result)) = futures.next().await {
+                        record(&mut results, path, result);
                     }
                 }
 
                 let context = Arc::clone(&context);
                 futures.push(async move {
-                    grep_file(path, &context.pattern, &context.options).await
+                    let result = grep_file(&path, &context.pattern, &context.options).await;
+                    (path, result)
                 });
             }
             Err(err) => {
-                eprintln!("Error walking directory: {}", err);
+                let path = walk_error_path(&err).unwrap_or(root.as_ref()).to_path_buf();
+                results.errors.push(GrepFileError { path, message: err.to_string() });
             }
         }
     }
 
     // Drain remaining futures
-    while let Some(result) = futures.next().await {
-        if let Ok(Some(file_match)) = result {
-            matches.push(file_match);
-        }
+    while let Some((path, result)) = futures.next().await {
+        record(&mut results, path, result);
     }
 
-    Ok(matches)
+    Ok(results)
 }
\ No newline at end of file
@@ -7,6 +7,8 @@ pub mod fs_write;
 pub mod fs_write_partial;
 pub mod fs_write_large_partial;
 pub mod grep;
+pub mod redact;
+mod sandbox;
 pub mod types;
 
 pub use error::*;
@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+use regex::Regex;
+
+/// Text that replaces every secret found in command output
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Bytes of a line held back when it has to be cut, for a pattern match
+/// that starts before the cut
+const PATTERN_OVERLAP: usize = 1024;
+
+/// Masks secret values and patterns in command output.
+///
+/// Output is redacted line by line as it is read, so a pattern can't match
+/// across lines. Lines too long to hold whole are cut where no secret
+/// crosses the cut, see `cut_point`.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    regex: Regex,
+    /// The same regex over bytes, for finding a cut in output that isn't
+    /// yet valid UTF-8
+    bytes: regex::bytes::Regex,
+    /// Longest literal secret, or `PATTERN_OVERLAP` if that's longer
+    overlap: usize,
+}
+
+impl Redactor {
+    /// Build a redactor from literal secret values and regexes, or `None` if
+    /// there is nothing to redact. Empty values are ignored.
+    pub fn new(values: &[String], patterns: &[String]) -> Result<Option<Self>, regex::Error> {
+        // Validate each pattern on its own so errors point at the right one
+        for pattern in patterns {
+            Regex::new(pattern)?;
+        }
+
+        let alternatives: Vec<String> = values
+            .iter()
+            .filter(|value| !value.is_empty())
+            .map(|value| regex::escape(value))
+            .chain(patterns.iter().cloned())
+            .map(|alternative| format!("(?:{})", alternative))
+            .collect();
+
+        if alternatives.is_empty() {
+            return Ok(None);
+        }
+
+        let pattern = alternatives.join("|");
+        let longest = values.iter().map(String::len).max().unwrap_or(0);
+        Ok(Some(Self {
+            regex: Regex::new(&pattern)?,
+            bytes: regex::bytes::Regex::new(&pattern)?,
+            overlap: longest.max(PATTERN_OVERLAP),
+        }))
+    }
+
+    /// Where to cut a partial line of `text` so each piece can be redacted
+    /// on its own: far enough from the end that a secret starting before
+    /// the cut ends before it too, and not through any secret that crosses
+    /// it. Returns 0 if the text can't be cut yet.
+    pub fn cut_point(&self, text: &[u8]) -> usize {
+        let mut cut = text.len().saturating_sub(self.overlap);
+        // Keep multi-byte characters whole
+        while cut > 0 && text[cut] & 0xC0 == 0x80 {
+            cut -= 1;
+        }
+        if cut == 0 {
+            return 0;
+        }
+        match self.bytes.find_iter(text).find(|m| m.start() < cut && m.end() > cut) {
+            None => cut,
+            // A match running to the end may go on in the next read
+            Some(m) if m.end() == text.len() && m.start() > 0 => m.start(),
+            Some(m) => m.end(),
+        }
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.regex.replace_all(text, REDACTED)
+    }
+}
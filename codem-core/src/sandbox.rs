@@ -0,0 +1,240 @@
+//! Confine a command to its project using Landlock and, optionally, a network namespace.
+//!
+//! Everything that can allocate (opening paths, building the ruleset,
+//! formatting id maps) happens in the parent before spawning. The child only
+//! makes raw syscalls between fork and exec.
+
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use crate::types::Sandbox;
+use crate::CommandError;
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+/// Available from Landlock ABI 2
+const ACCESS_FS_REFER: u64 = 1 << 13;
+/// Available from Landlock ABI 3
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+/// Rights that can be granted on a file (as opposed to a directory)
+const FILE_WRITE_ACCESS: u64 = ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE;
+
+/// Devices commands commonly write to that don't modify anything
+const WRITABLE_DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/full", "/dev/tty"];
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// A sandbox that has been set up in the parent and can be entered by the child
+pub(crate) struct PreparedSandbox {
+    ruleset: OwnedFd,
+    network: Option<NetworkNamespace>,
+}
+
+/// How the child detaches from the network. The id maps keep its uid and gid
+/// when a user namespace has to be created as well.
+struct NetworkNamespace {
+    user_namespace: bool,
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+}
+
+fn unavailable(reason: impl Into<String>) -> CommandError {
+    CommandError::SandboxUnavailable { reason: reason.into() }
+}
+
+fn create_ruleset(handled_access_fs: u64) -> Result<OwnedFd, CommandError> {
+    let attr = RulesetAttr { handled_access_fs };
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0u32,
+        )
+    };
+    if fd < 0 {
+        return Err(unavailable(format!(
+            "could not create Landlock ruleset: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+fn add_path_rule(ruleset: &OwnedFd, path: &Path, handled_access_fs: u64) -> Result<(), CommandError> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| unavailable(format!("invalid path: {}", path.display())))?;
+
+    let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(unavailable(format!(
+            "could not open {}: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        )));
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let allowed_access = if path.is_dir() {
+        handled_access_fs
+    } else {
+        handled_access_fs & FILE_WRITE_ACCESS
+    };
+    let attr = PathBeneathAttr {
+        allowed_access,
+        parent_fd: fd.as_raw_fd(),
+    };
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            LANDLOCK_RULE_PATH_BENEATH,
+            &attr as *const PathBeneathAttr,
+            0u32,
+        )
+    };
+    if result < 0 {
+        return Err(unavailable(format!(
+            "could not allow writes to {}: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// Build the Landlock ruleset and everything else the child needs.
+///
+/// Fails with `SandboxUnavailable` rather than running the command without
+/// the requested confinement.
+pub(crate) fn prepare(sandbox: &Sandbox, scratch_dir: &Path) -> Result<PreparedSandbox, CommandError> {
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        return Err(unavailable(format!(
+            "Landlock is not supported by this kernel: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    let mut handled_access_fs = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_REMOVE_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_CHAR
+        | ACCESS_FS_MAKE_DIR
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_MAKE_SOCK
+        | ACCESS_FS_MAKE_FIFO
+        | ACCESS_FS_MAKE_BLOCK
+        | ACCESS_FS_MAKE_SYM;
+    if abi >= 2 {
+        handled_access_fs |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled_access_fs |= ACCESS_FS_TRUNCATE;
+    }
+
+    let ruleset = create_ruleset(handled_access_fs)?;
+    for path in &sandbox.writable_paths {
+        add_path_rule(&ruleset, path, handled_access_fs)?;
+    }
+    add_path_rule(&ruleset, scratch_dir, handled_access_fs)?;
+    for device in WRITABLE_DEVICES {
+        let device = Path::new(device);
+        if device.exists() {
+            add_path_rule(&ruleset, device, handled_access_fs)?;
+        }
+    }
+
+    let network = (!sandbox.allow_network).then(|| {
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        NetworkNamespace {
+            // Root can create a network namespace directly; anyone else needs
+            // a user namespace first
+            user_namespace: uid != 0,
+            uid_map: format!("{uid} {uid} 1\n").into_bytes(),
+            gid_map: format!("{gid} {gid} 1\n").into_bytes(),
+        }
+    });
+
+    Ok(PreparedSandbox { ruleset, network })
+}
+
+/// Write `data` to a NUL-terminated `path` using only syscalls
+fn write_proc_file(path: &[u8], data: &[u8]) -> std::io::Result<()> {
+    let fd = unsafe { libc::open(path.as_ptr().cast(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let written = unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) };
+    let error = std::io::Error::last_os_error();
+    unsafe {
+        libc::close(fd);
+    }
+    if written != data.len() as isize {
+        return Err(error);
+    }
+    Ok(())
+}
+
+impl PreparedSandbox {
+    /// Enter the sandbox. Only makes syscalls, so it is safe to call after fork.
+    pub(crate) fn enter(&self) -> std::io::Result<()> {
+        if let Some(network) = &self.network {
+            let flags = if network.user_namespace {
+                libc::CLONE_NEWUSER | libc::CLONE_NEWNET
+            } else {
+                libc::CLONE_NEWNET
+            };
+            if unsafe { libc::unshare(flags) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if network.user_namespace {
+                write_proc_file(b"/proc/self/setgroups\0", b"deny")?;
+                write_proc_file(b"/proc/self/uid_map\0", &network.uid_map)?;
+                write_proc_file(b"/proc/self/gid_map\0", &network.gid_map)?;
+            }
+        }
+
+        if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let result = unsafe {
+            libc::syscall(libc::SYS_landlock_restrict_self, self.ruleset.as_raw_fd(), 0u32)
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
-use crate::command::run_command;
+use crate::command::{run_command, run_command_with_options};
+use std::sync::Arc;
+use crate::redact::Redactor;
+use crate::types::{CommandEnv, CommandOptions, OutputStream, ResourceLimit, ResourceLimits, Sandbox};
 use tempfile::TempDir;
 
 #[tokio::test]
 async fn test_command_timeout() -> anyhow::Result<()> {
-    let result = run_command("sleep 2", None, Some(100)).await;
+    let result = run_command("sleep 2", None, Some(100), None).await;
 
     assert!(matches!(
         result,
@@ -18,7 +21,7 @@ async fn test_command_timeout() -> anyhow::Result<()> {
 
 #[tokio::test]
 async fn test_command_fails() -> anyhow::Result<()> {
-    let result = run_command("ls nonexistent_file", None, None).await.unwrap();
+    let result = run_command("ls nonexistent_file", None, None, None).await.unwrap();
 
     assert!(result.exit_code != 0);
 
@@ -31,7 +34,7 @@ async fn test_command_with_cwd() -> anyhow::Result<()> {
     let path = temp.path().to_path_buf();
 
     // On Linux, pwd writes to stdout, on Windows, cd writes to stderr
-    let result = run_command("pwd", Some(&path), None).await?;
+    let result = run_command("pwd", Some(&path), None, None).await?;
 
     let actual = result.stdout.trim();
     let path_str = temp.path().to_string_lossy();
@@ -41,3 +44,384 @@ async fn test_command_with_cwd() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_command_streams_output() -> anyhow::Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let options = CommandOptions {
+        output: Some(tx),
+        ..Default::default()
+    };
+
+    let result = run_command_with_options("echo one; echo two >&2; echo three", &options).await?;
+    drop(options);
+
+    let mut chunks = Vec::new();
+    while let Some(chunk) = rx.recv().await {
+        chunks.push(chunk);
+    }
+
+    let stdout: Vec<_> = chunks.iter()
+        .filter(|c| c.stream == OutputStream::Stdout)
+        .map(|c| c.text.as_str())
+        .collect();
+    let stderr: Vec<_> = chunks.iter()
+        .filter(|c| c.stream == OutputStream::Stderr)
+        .map(|c| c.text.as_str())
+        .collect();
+
+    assert_eq!(stdout, vec!["one\n", "three\n"]);
+    assert_eq!(stderr, vec!["two\n"]);
+
+    // The final output still contains everything
+    assert_eq!(result.stdout, "one\nthree\n");
+    assert_eq!(result.stderr, "two\n");
+
+    Ok(())
+}
+
+fn process_is_gone(pid: i32) -> bool {
+    // A killed process may linger briefly as a zombie until it is reaped
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat.rsplit(')').next().is_some_and(|rest| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[tokio::test]
+async fn test_command_timeout_kills_process_group() -> anyhow::Result<()> {
+    let start = std::time::Instant::now();
+    let result = run_command("sleep 30 & echo $!; wait", None, Some(200), None).await;
+
+    let Err(crate::error::CommandError::Timeout { stdout, .. }) = result else {
+        panic!("Expected timeout, got {:?}", result);
+    };
+
+    // The grandchild must not keep the pipes (and us) waiting
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    let pid: i32 = stdout.trim().parse()?;
+    for _ in 0..50 {
+        if process_is_gone(pid) {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("Background process {} survived the timeout", pid);
+}
+
+#[tokio::test]
+async fn test_cancelled_command_kills_process_group() -> anyhow::Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let options = CommandOptions {
+        output: Some(tx),
+        ..Default::default()
+    };
+
+    let task = tokio::spawn(async move {
+        run_command_with_options("sleep 30 & echo $!; wait", &options).await
+    });
+
+    let chunk = rx.recv().await.expect("pid should be printed");
+    let pid: i32 = chunk.text.trim().parse()?;
+
+    // Dropping the future must take the whole tree down with it
+    task.abort();
+    for _ in 0..50 {
+        if process_is_gone(pid) {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("Background process {} survived cancellation", pid);
+}
+
+#[tokio::test]
+async fn test_background_process_does_not_block_completion() -> anyhow::Result<()> {
+    let start = std::time::Instant::now();
+    let result = run_command("sleep 30 & echo done", None, None, None).await?;
+
+    assert_eq!(result.stdout, "done\n");
+    assert_eq!(result.exit_code, 0);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_stdin() -> anyhow::Result<()> {
+    let result = run_command("tr a-z A-Z", None, Some(5000), Some("hello\nworld")).await?;
+    assert_eq!(result.stdout, "HELLO\nWORLD");
+
+    // Larger than a pipe buffer, so it must be written while output is read
+    let input = "x".repeat(1024 * 1024);
+    let result = run_command("cat", None, Some(5000), Some(&input)).await?;
+    assert_eq!(result.stdout.len(), input.len());
+
+    // Without input, stdin is empty rather than inherited
+    let result = run_command("cat", None, Some(5000), None).await?;
+    assert_eq!(result.stdout, "");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_output_is_capped() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        limits: ResourceLimits {
+            max_output_bytes: Some(100),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let output = run_command_with_options("head -c 10000 /dev/zero | tr '\\0' x", &options).await?;
+
+    assert_eq!(output.exit_code, 0);
+    assert_eq!(output.stdout, "x".repeat(100));
+    assert_eq!(output.limits_hit, vec![ResourceLimit::Output]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_cpu_limit() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        timeout_ms: Some(10_000),
+        limits: ResourceLimits {
+            cpu_seconds: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = run_command_with_options("while :; do :; done", &options).await;
+
+    assert!(matches!(
+        result,
+        Err(crate::error::CommandError::LimitExceeded {
+            limit: ResourceLimit::CpuTime,
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_file_size_limit() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let options = CommandOptions {
+        cwd: Some(temp.path().to_path_buf()),
+        limits: ResourceLimits {
+            file_size_bytes: Some(1000),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // exec, so the signal reaches us rather than becoming the shell's exit code
+    let result = run_command_with_options("exec head -c 10000 /dev/zero > big", &options).await;
+
+    assert!(matches!(
+        result,
+        Err(crate::error::CommandError::LimitExceeded {
+            limit: ResourceLimit::FileSize,
+            ..
+        })
+    ));
+    assert!(std::fs::metadata(temp.path().join("big"))?.len() <= 1000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exit_code_is_not_taken_for_a_limit() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        limits: ResourceLimits {
+            cpu_seconds: Some(10),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // 128 + SIGXCPU, but from a plain exit
+    let output = run_command_with_options("exit 152", &options).await?;
+    assert_eq!(output.exit_code, 152);
+    assert!(output.limits_hit.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_long_line_output_is_capped() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        limits: ResourceLimits {
+            max_output_bytes: Some(100),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // Far more than is ever buffered, with no newline to end the line
+    let output = run_command_with_options("head -c 50000000 /dev/zero | tr '\\0' x", &options).await?;
+
+    assert_eq!(output.stdout, "x".repeat(100));
+    assert_eq!(output.limits_hit, vec![ResourceLimit::Output]);
+
+    Ok(())
+}
+
+fn sandboxed(writable: &std::path::Path, allow_network: bool) -> CommandOptions {
+    CommandOptions {
+        cwd: Some(writable.to_path_buf()),
+        sandbox: Some(Sandbox {
+            writable_paths: vec![writable.to_path_buf()],
+            allow_network,
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_sandbox_allows_writes_to_writable_paths() -> anyhow::Result<()> {
+    let project = TempDir::new()?;
+    let options = sandboxed(project.path(), true);
+
+    let output = run_command_with_options(
+        "echo hi > inside && echo tmp > \"$TMPDIR/scratch\" && cat \"$TMPDIR/scratch\" > /dev/null",
+        &options,
+    )
+    .await?;
+
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr);
+    assert_eq!(std::fs::read_to_string(project.path().join("inside"))?, "hi\n");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sandbox_blocks_writes_elsewhere() -> anyhow::Result<()> {
+    let project = TempDir::new()?;
+    let outside = TempDir::new()?;
+    std::fs::write(outside.path().join("existing"), "original")?;
+    let options = sandboxed(project.path(), true);
+
+    let command = format!(
+        "echo changed > {dir}/existing; touch {dir}/new; rm -f {dir}/existing",
+        dir = outside.path().display()
+    );
+    run_command_with_options(&command, &options).await?;
+
+    assert_eq!(std::fs::read_to_string(outside.path().join("existing"))?, "original");
+    assert!(!outside.path().join("new").exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sandbox_without_network() -> anyhow::Result<()> {
+    let project = TempDir::new()?;
+
+    // /proc/net/dev lists the interfaces of the current network namespace
+    let interfaces = "tail -n +3 /proc/net/dev | cut -d: -f1 | tr -d ' '";
+    let output = run_command_with_options(interfaces, &sandboxed(project.path(), false)).await?;
+
+    assert_eq!(output.stdout.trim(), "lo");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_env_set_and_unset() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        env: CommandEnv {
+            set: [("CODEM_TEST_VAR".to_string(), "set".to_string())].into(),
+            unset: vec!["HOME".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let output = run_command_with_options("echo \"$CODEM_TEST_VAR:${HOME-unset}\"", &options).await?;
+
+    assert_eq!(output.stdout, "set:unset\n");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_env_clear() -> anyhow::Result<()> {
+    let options = CommandOptions {
+        env: CommandEnv {
+            clear: true,
+            pass: vec!["HOME".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let output = run_command_with_options("env | cut -d= -f1 | grep -v '^PWD$' | sort", &options).await?;
+
+    let mut expected = vec!["PATH"];
+    if std::env::var_os("HOME").is_some() {
+        expected.insert(0, "HOME");
+    }
+    assert_eq!(output.stdout.lines().collect::<Vec<_>>(), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_redacts_secrets() -> anyhow::Result<()> {
+    let redactor = Redactor::new(&["hunter2".to_string()], &[r"tok_[a-z0-9]+".to_string()])?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let options = CommandOptions {
+        output: Some(tx),
+        redactor: redactor.map(Arc::new),
+        ..Default::default()
+    };
+
+    let output = run_command_with_options(
+        "echo password=hunter2; echo token=tok_abc123 >&2",
+        &options,
+    )
+    .await?;
+    drop(options);
+
+    assert_eq!(output.stdout, "password=[REDACTED]\n");
+    assert_eq!(output.stderr, "token=[REDACTED]\n");
+
+    let mut streamed = String::new();
+    while let Some(chunk) = rx.recv().await {
+        streamed.push_str(&chunk.text);
+    }
+    assert!(!streamed.contains("hunter2"));
+    assert!(!streamed.contains("tok_abc123"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_redacts_secrets_across_long_line_cuts() -> anyhow::Result<()> {
+    let redactor = Redactor::new(&["hunter2".to_string()], &[r"tok_[a-z0-9]+".to_string()])?;
+    let options = CommandOptions {
+        redactor: redactor.map(Arc::new),
+        ..Default::default()
+    };
+
+    // Secrets straddling the 64 KiB point where an unbroken line is first
+    // cut, and one that runs up to the end of the next cut
+    for (offset, secret) in [(65533, "hunter2"), (65530, "tok_abcdefghij"), (130040, "tok_abcdefghij")] {
+        let command = format!(
+            "head -c {} /dev/zero | tr '\\0' x; printf {}; head -c 70000 /dev/zero | tr '\\0' Y",
+            offset, secret
+        );
+        let output = run_command_with_options(&command, &options).await?;
+        assert!(!output.stdout.contains(&secret[..4]), "{} leaked", secret);
+        assert_eq!(output.stdout.matches("[REDACTED]").count(), 1);
+        assert_eq!(output.stdout.len(), offset + "[REDACTED]".len() + 70000);
+    }
+
+    Ok(())
+}
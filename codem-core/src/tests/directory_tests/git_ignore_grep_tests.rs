@@ -32,9 +32,10 @@ async fn test_git_directory_ignored_in_grep() {
         context_lines: 0,
         file_pattern: None,
         case_sensitive: true,
+        ..Default::default()
     };
 
-    let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap();
+    let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap().files;
     
     // Should find content only in normal files, not in .git
     assert_eq!(results.len(), 1, "Should only find content in non-git files");
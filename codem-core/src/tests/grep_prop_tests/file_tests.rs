@@ -49,12 +49,22 @@ proptest! {
             };
 
             if let Ok(result) = grep_codebase(dir.path(), &regex, &options).await {
-                if let Some(file_match) = result.first() {
+                if let Some(file_match) = result.files.first() {
+                    let mut previous_end = 0;
                     for grep_match in &file_match.matches {
-                        let context_lines = grep_match.context.lines().count();
-                        // Context includes the match line plus context lines before and after
-                        assert!(context_lines <= 1 + 2 * options.context_lines,
-                            "Context should not exceed match line plus context lines");
+                        let first = *grep_match.match_lines.first().unwrap();
+                        let last = *grep_match.match_lines.last().unwrap();
+                        // Context covers the match lines plus context lines before and after
+                        assert!(grep_match.context_start + options.context_lines >= first,
+                            "Context should not start more than context lines before the first match");
+                        assert!(grep_match.context_start + grep_match.context.split('\n').count() <= last + options.context_lines + 1,
+                            "Context should not end more than context lines after the last match");
+                        // Windows that overlap or touch are merged into one block
+                        if options.context_lines > 0 && previous_end > 0 {
+                            assert!(grep_match.context_start > previous_end + 1,
+                                "Blocks should be separated by at least one line");
+                        }
+                        previous_end = grep_match.context_start + grep_match.context.split('\n').count() - 1;
                     }
                 }
             }
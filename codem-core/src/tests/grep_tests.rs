@@ -21,7 +21,7 @@ mod tests {
 
         let pattern = Regex::new("line").unwrap();
         let options = GrepOptions::default();
-        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap();
+        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap().files;
         
         assert_eq!(results.len(), 1, "Should have found matches in one file");
         let file_match = &results[0];
@@ -40,7 +40,7 @@ mod tests {
 
         let pattern = Regex::new("line").unwrap();
         let options = GrepOptions::default();
-        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap();
+        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap().files;
         assert!(results.is_empty(), "Should not have found any matches");
     }
 
@@ -65,10 +65,11 @@ mod tests {
             context_lines: 0,
             file_pattern: None,
             case_sensitive: true,
+            ..Default::default()
         };
 
         let pattern = Regex::new("line").unwrap();
-        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap();
+        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap().files;
 
         assert_eq!(results.len(), 2, "Should have found matches in two files");
         assert!(results.iter().any(|m| m.path == temp.path().join("test1.txt")));
@@ -80,4 +81,109 @@ mod tests {
         let file3_matches = results.iter().find(|m| m.path == temp.path().join("subdir/test3.txt")).unwrap();
         assert_eq!(file3_matches.matches.len(), 2);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_grep_match_spans() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("test.txt");
+        fs::write(&file, "no hits
+naïve Line, line
+").unwrap();
+
+        let pattern = Regex::new("line").unwrap();
+        let results = crate::grep::grep_file(&file, &pattern, &GrepOptions::default()).await.unwrap().unwrap();
+
+        let spans = &results.matches[0].spans;
+        let ranges: Vec<_> = spans.iter().map(|s| (s.start, s.end, s.char_start, s.char_end)).collect();
+        assert_eq!(ranges, vec![(7, 11, 6, 10), (13, 17, 12, 16)]);
+        // Case-insensitive by default, so the text is what's in the file
+        let texts: Vec<_> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Line", "line"]);
+    }
+
+    #[tokio::test]
+    async fn test_grep_merges_overlapping_context() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("test.txt");
+        fs::write(&file, "hit 1\na\nhit 3\nb\nc\nd\ne\nhit 8\nf\n").unwrap();
+
+        let pattern = Regex::new("hit").unwrap();
+        let options = GrepOptions { context_lines: 1, ..Default::default() };
+        let results = crate::grep::grep_file(&file, &pattern, &options).await.unwrap().unwrap();
+
+        // Lines 1 and 3 share a block; line 8's window doesn't reach line 4
+        assert_eq!(results.matches.len(), 2);
+        let first = &results.matches[0];
+        assert_eq!((first.context_start, first.context.as_str()), (1, "hit 1\na\nhit 3\nb"));
+        assert_eq!(first.match_lines, vec![1, 3]);
+        let spans: Vec<_> = first.spans.iter().map(|s| s.line_number).collect();
+        assert_eq!(spans, vec![1, 3]);
+        let second = &results.matches[1];
+        assert_eq!((second.context_start, second.context.as_str()), (7, "e\nhit 8\nf"));
+        assert_eq!(second.match_lines, vec![8]);
+    }
+
+    #[tokio::test]
+    async fn test_grep_modes() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("test.rs");
+        fs::write(&file, "foo.bar(1);\nfoo_bar(2);\nlet bar = foo\n    .bar(3);\n").unwrap();
+
+        let lines = |options: GrepOptions, pattern: &str| {
+            let file = file.clone();
+            let pattern = crate::grep::build_pattern(pattern, &options).unwrap();
+            async move {
+                crate::grep::grep_file(&file, &pattern, &options).await.unwrap()
+                    .map(|m| m.matches.iter().flat_map(|m| m.match_lines.clone()).collect::<Vec<_>>())
+                    .unwrap_or_default()
+            }
+        };
+
+        // As a regex, `.` matches the underscore too
+        assert_eq!(lines(GrepOptions::default(), "foo.bar").await, vec![1, 2]);
+        let fixed = GrepOptions { fixed_string: true, ..Default::default() };
+        assert_eq!(lines(fixed, "foo.bar(").await, vec![1]);
+
+        let word = GrepOptions { whole_word: true, ..Default::default() };
+        assert_eq!(lines(word, "bar").await, vec![1, 3, 4]);
+
+        let multiline = GrepOptions { multiline: true, ..Default::default() };
+        assert_eq!(lines(multiline.clone(), r"foo\n\s*\.bar").await, vec![3, 4]);
+        let results = crate::grep::grep_file(&file, &Regex::new(r"foo\n\s*\.bar").unwrap(), &multiline).await.unwrap().unwrap();
+        let span = &results.matches[0].spans[0];
+        assert_eq!((span.line_number, span.start, span.char_start), (3, 10, 10));
+        assert_eq!(span.text, "foo\n    .bar");
+    }
+
+    #[tokio::test]
+    async fn test_grep_encodings_and_binary() {
+        let temp = TempDir::new().unwrap();
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter()
+            .chain("first\nna\u{ef}ve needle\n".encode_utf16().flat_map(|u| u.to_le_bytes()))
+            .collect();
+        fs::write(temp.path().join("utf16.txt"), utf16).unwrap();
+        fs::write(temp.path().join("latin1.txt"), b"caf\xe9 needle\n").unwrap();
+        fs::write(temp.path().join("blob.bin"), b"\x00\x01needle\x02").unwrap();
+
+        let pattern = Regex::new("needle").unwrap();
+        let results = grep_codebase(temp.path(), &pattern, &GrepOptions::default()).await.unwrap();
+        assert!(results.errors.is_empty());
+        let mut files: Vec<_> = results.files.iter()
+            .map(|m| (m.path.file_name().unwrap().to_str().unwrap(), m.encoding.as_deref(), m.matches[0].context.as_str()))
+            .collect();
+        files.sort();
+        // Binary files are skipped unless asked for
+        assert_eq!(files, vec![
+            ("latin1.txt", Some("windows-1252"), "caf\u{e9} needle"),
+            ("utf16.txt", Some("UTF-16LE"), "na\u{ef}ve needle"),
+        ]);
+
+        let options = GrepOptions { search_binary: true, ..Default::default() };
+        let results = grep_codebase(temp.path(), &pattern, &options).await.unwrap();
+        let binary = results.files.iter().find(|m| m.binary).unwrap();
+        assert_eq!(binary.path, temp.path().join("blob.bin"));
+        assert!(binary.matches.is_empty());
+        let missing = Regex::new("haystack").unwrap();
+        assert!(crate::grep::grep_file(temp.path().join("blob.bin"), &missing, &options).await.unwrap().is_none());
+    }
+}
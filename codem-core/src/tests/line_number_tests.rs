@@ -1,6 +1,3 @@
-use super::*;
-use std::fs;
-use tempfile::TempDir;
 use crate::{fs_write_partial::find::{get_line_number, is_within_line_range}, types::LineRange};
 
 #[test]
@@ -95,8 +92,7 @@ fn test_line_count_at_boundaries() {
         // "Line 3\n" - everything up to final \n is line 3
         let expected = if i <= 6 { 1 }          // Up to and including first \n
                       else if i <= 13 { 2 }      // Up to and including second \n  
-                      else if i <= 20 { 3 }      // Up to and including third \n
-                      else { 3 };                // After final newline
+                      else { 3 };                // Up to and including third \n, and after it
                       
         assert_eq!(line, expected, 
             "Wrong line number {} for position {} (char '{}'), expected {}", 
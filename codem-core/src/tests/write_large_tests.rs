@@ -1,7 +1,6 @@
 use rstest::rstest;
 use tokio::fs;
 use crate::types::{PartialWriteLarge, WriteOperation, LineRange};
-use crate::WriteError;
 use crate::fs_write::write_file;
 use tempfile::TempDir;
 
@@ -49,7 +48,7 @@ async fn test_pattern_validation(#[case] start: &str, #[case] end: &str, #[case]
 
     let _result = write_file(&file_path, operation, None).await.unwrap();
     let final_content = fs::read_to_string(&file_path).await.unwrap();
-    assert!(final_content.contains(&new_content));
+    assert!(final_content.contains(new_content));
 }
 
 #[tokio::test]
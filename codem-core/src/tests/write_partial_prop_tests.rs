@@ -2,7 +2,6 @@ use proptest::prelude::*;
 use crate::types::{PartialWrite, WriteOperation, Change, WriteResultDetails};
 use tempfile::TempDir;
 use crate::fs_write::write_file;
-use tokio;
 
 proptest! {
     #[test]
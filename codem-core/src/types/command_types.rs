@@ -1,6 +1,132 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::redact::Redactor;
+
 #[derive(Debug, Clone)]
 pub struct CommandOutput {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
-}
\ No newline at end of file
+    /// Limits the command ran into without being killed (e.g. output was truncated)
+    pub limits_hit: Vec<ResourceLimit>,
+}
+
+/// Resource limits applied to a command and everything it spawns.
+///
+/// The rlimits are per process, except `max_processes` which (like
+/// `RLIMIT_NPROC`) counts every process of the user and is not enforced for
+/// root. `max_output_bytes` caps how much of each of stdout and stderr is
+/// captured; the command keeps running, but further output is discarded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub cpu_seconds: Option<u64>,
+    pub address_space_bytes: Option<u64>,
+    pub file_size_bytes: Option<u64>,
+    pub max_processes: Option<u64>,
+    pub max_output_bytes: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// Whether any limit has to be set on the child process itself
+    pub fn has_rlimits(&self) -> bool {
+        self.cpu_seconds.is_some()
+            || self.address_space_bytes.is_some()
+            || self.file_size_bytes.is_some()
+            || self.max_processes.is_some()
+    }
+}
+
+/// A limit that was detectably hit while running a command.
+///
+/// Exhausting the address space or process count only makes allocations or
+/// forks fail inside the command, so those can't be reported reliably.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    CpuTime,
+    FileSize,
+    Output,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::CpuTime => write!(f, "CPU time limit"),
+            ResourceLimit::FileSize => write!(f, "file size limit"),
+            ResourceLimit::Output => write!(f, "output size limit"),
+        }
+    }
+}
+
+/// Which pipe of the child process a chunk of output was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl fmt::Display for OutputStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputStream::Stdout => write!(f, "stdout"),
+            OutputStream::Stderr => write!(f, "stderr"),
+        }
+    }
+}
+
+/// A piece of output (usually a single line) read while the command is running
+#[derive(Debug, Clone)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    pub text: String,
+}
+
+/// Channel used to receive output from a command while it is still executing
+pub type OutputSender = UnboundedSender<OutputChunk>;
+
+/// Environment variables for a command.
+///
+/// By default the command inherits the server's environment. With `clear`,
+/// it starts from an empty one instead, keeping only the variables in `pass`
+/// (and a default `PATH` unless `PATH` is passed or set). `unset` is applied
+/// before `set`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandEnv {
+    pub clear: bool,
+    pub pass: Vec<String>,
+    pub set: BTreeMap<String, String>,
+    pub unset: Vec<String>,
+}
+
+/// Confinement for a command and everything it spawns (Linux only).
+///
+/// The whole filesystem stays readable, but only `writable_paths` and a
+/// scratch directory (exposed as `TMPDIR`) can be written to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    pub writable_paths: Vec<PathBuf>,
+    /// If false, the command runs in its own network namespace with no interfaces up
+    pub allow_network: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions {
+    pub cwd: Option<PathBuf>,
+    pub timeout_ms: Option<u64>,
+    /// If set, each line of stdout/stderr is sent here as soon as it is read
+    pub output: Option<OutputSender>,
+    pub limits: ResourceLimits,
+    pub sandbox: Option<Sandbox>,
+    pub env: CommandEnv,
+    /// If set, secrets are masked in stdout/stderr before they are captured or streamed
+    pub redactor: Option<Arc<Redactor>>,
+    /// Written to the command's stdin, which is then closed. Without it the
+    /// command gets an empty stdin.
+    pub stdin: Option<String>,
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 use std::time::SystemTime;
-use crate::types::FileMetadata;
+use crate::types::{FileMetadata, PathFilter};
 
 #[derive(Debug, Default, Clone)]
 pub struct ListOptions {
@@ -9,6 +9,7 @@ pub struct ListOptions {
     pub file_pattern: Option<String>,
     pub recursive: bool,
     pub count_lines: bool,
+    pub path_filter: Option<PathFilter>,
 }
 
 #[derive(Debug, Clone)]
@@ -115,6 +115,11 @@ pub enum WriteResultDetails {
         output: String,
         details: Box<WriteResultDetails>,
     },
+    WithCheckOutput {
+        output: String,
+        passed: bool,
+        details: Box<WriteResultDetails>,
+    },
 }
 
 #[derive(Debug)]
@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+use ignore::overrides::{Override, OverrideBuilder};
+
+/// Gitignore-style globs choosing which paths a search or listing covers.
+/// Globs are matched against paths relative to `base_path`.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct PathFilter {
+    pub base_path: PathBuf,
+    /// If any are given, only files matching one of them are included
+    pub include: Vec<String>,
+    /// Files and directories matching any of these are skipped
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn new(base_path: impl AsRef<Path>, include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            base_path: base_path.as_ref().to_path_buf(),
+            include,
+            exclude,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Walker overrides applying the globs
+    pub fn overrides(&self) -> Result<Override, ignore::Error> {
+        let mut builder = OverrideBuilder::new(&self.base_path);
+        for glob in &self.include {
+            builder.add(glob)?;
+        }
+        // Override globs are whitelists unless negated
+        for glob in &self.exclude {
+            builder.add(&format!("!{}", glob))?;
+        }
+        builder.build()
+    }
+}
@@ -1,20 +1,74 @@
 use std::path::PathBuf;
+use serde::Serialize;
+use crate::types::PathFilter;
 
-#[derive(Debug, Clone)]
+/// One hit of the pattern within a matching line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepSpan {
+    pub line_number: usize,
+    /// Byte offsets into the line; a multiline hit ends past the line
+    pub start: usize,
+    pub end: usize,
+    /// Character offsets into the line
+    pub char_start: usize,
+    pub char_end: usize,
+    pub text: String,
+}
+
+/// A block of context around one or more matching lines. When context lines
+/// are requested, matches whose windows overlap or touch share a block.
+#[derive(Debug, Clone, Serialize)]
 pub struct GrepMatch {
+    /// First matching line in the block
     pub line_number: usize,
+    /// Line number of the first line of `context`
+    pub context_start: usize,
     pub context: String,
+    /// Every matching line in the block, in order
+    pub match_lines: Vec<usize>,
+    /// Every hit on the block's matching lines, in order
+    pub spans: Vec<GrepSpan>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct GrepFileMatch {
     pub path: PathBuf,
     pub matches: Vec<GrepMatch>,
+    /// The file is binary and contains the pattern; `matches` is empty
+    pub binary: bool,
+    /// Encoding the file was transcoded from, if it wasn't UTF-8. Span
+    /// offsets are into the transcoded text.
+    pub encoding: Option<String>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A file that couldn't be searched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepFileError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Results of searching a directory tree
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GrepCodebaseResult {
+    pub files: Vec<GrepFileMatch>,
+    /// Files and directories that couldn't be read
+    pub errors: Vec<GrepFileError>,
+}
+
+#[derive(Debug, Clone, Default, Hash)]
 pub struct GrepOptions {
     pub context_lines: usize,
     pub file_pattern: Option<String>,
     pub case_sensitive: bool,
-}
\ No newline at end of file
+    /// Match the pattern literally rather than as a regex
+    pub fixed_string: bool,
+    /// Only match the pattern at word boundaries
+    pub whole_word: bool,
+    /// Let the pattern match across line breaks
+    pub multiline: bool,
+    /// Search binary files too, reporting only whether each one matches;
+    /// otherwise they're skipped
+    pub search_binary: bool,
+    pub path_filter: Option<PathFilter>,
+}
@@ -1,9 +1,11 @@
 mod command_types;
 mod directory_types;
 mod file_types;
+mod filter_types;
 mod grep_types;
 
 pub use command_types::*;
 pub use directory_types::{TreeEntry, ListEntry, ListOptions};
 pub use file_types::*;
+pub use filter_types::*;
 pub use grep_types::*;
\ No newline at end of file
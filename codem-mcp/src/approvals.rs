@@ -0,0 +1,64 @@
+use std::path::Path;
+use anyhow::Result;
+use clap::Subcommand;
+use codem_client::{approval::ApprovalQueue, types::{ApprovalRequest, ApprovalStatus}};
+
+// Review risky commands queued for approval
+#[derive(Subcommand)]
+pub enum ApprovalCommand {
+    /// List commands waiting for approval
+    Pending,
+    /// Let a queued command run
+    Approve {
+        id: u64,
+    },
+    /// Refuse a queued command
+    Deny {
+        id: u64,
+        /// Shown to the agent
+        #[arg(long)]
+        reason: Option<String>,
+    },
+}
+
+fn format_request(request: &ApprovalRequest) -> String {
+    let mut text = format!(
+        "{} [{}] {} (project {}, session {}, in {})",
+        request.id,
+        request.status,
+        request.command,
+        request.project,
+        request.session_id,
+        request.cwd.display()
+    );
+    if let Some(stdin) = &request.stdin {
+        text.push_str(&format!("\n    stdin: {:?}", stdin));
+    }
+    text
+}
+
+pub async fn run(command: ApprovalCommand, session_file: &Path) -> Result<()> {
+    let queue = ApprovalQueue::new(session_file);
+
+    match command {
+        ApprovalCommand::Pending => {
+            let pending: Vec<_> = queue.list().await?
+                .into_iter()
+                .filter(|r| r.status == ApprovalStatus::Pending)
+                .collect();
+            if pending.is_empty() {
+                println!("No commands are waiting for approval");
+            }
+            for request in pending {
+                println!("{}", format_request(&request));
+            }
+        }
+        ApprovalCommand::Approve { id } => {
+            println!("{}", format_request(&queue.decide(id, true, None).await?));
+        }
+        ApprovalCommand::Deny { id, reason } => {
+            println!("{}", format_request(&queue.decide(id, false, reason.as_deref()).await?));
+        }
+    }
+    Ok(())
+}
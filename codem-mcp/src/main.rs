@@ -4,6 +4,7 @@ use anyhow::{Result, Context};
 use tracing::{info, Level};
 use tracing_subscriber::{self, fmt::format::FmtSpan};
 
+mod approvals;
 mod error;
 mod server;
 mod tools;
@@ -11,6 +12,7 @@ mod config;
 
 #[cfg(test)]
 mod tests;
+use approvals::ApprovalCommand;
 use config::TomlConfig;
 
 #[derive(Parser)]
@@ -19,6 +21,10 @@ struct Cli {
     /// Path to codem config file
     #[arg(value_name = "CONFIG_FILE")]
     config: PathBuf,
+
+    /// Review queued risky commands instead of starting the server
+    #[command(subcommand)]
+    approvals: Option<ApprovalCommand>,
 }
 
 #[tokio::main]
@@ -45,6 +51,10 @@ async fn main() -> Result<()> {
     // First parse into intermediate TOML format
     let toml_config: TomlConfig = toml::from_str(&config_str)
         .context("Failed to parse config file")?;
+
+    if let Some(command) = cli.approvals {
+        return approvals::run(command, &toml_config.session_file).await;
+    }
         
     // Then convert to ClientConfig, which performs validation
     let config = toml_config.into_client_config().await
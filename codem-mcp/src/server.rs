@@ -6,13 +6,6 @@ use tracing::info;
 
 use crate::tools;
 
-#[cfg(test)]
-use {
-    anyhow::Result as AnyhowResult,
-    std::{fs, path::Path},
-    tempfile::TempDir,
-};
-
 pub struct Mcp {
     pub(crate) client: Client,
 }
@@ -1,4 +1,5 @@
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn successful_test() {
     assert!(true, "This test should pass");
 }
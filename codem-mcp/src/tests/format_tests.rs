@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+use codem_core::types::{GrepFileError, GrepFileMatch, GrepMatch, GrepSpan};
+use codem_client::types::{CappedFile, FileReplacement, GrepPage, ReplaceHunk, ReplaceResult};
+use crate::tools::format::{format_grep_page, format_grep_results, format_replace_result, GrepFormat};
+
+fn span(line_number: usize, start: usize, text: &str) -> GrepSpan {
+    GrepSpan {
+        line_number,
+        start,
+        end: start + text.len(),
+        char_start: start,
+        char_end: start + text.len(),
+        text: text.to_string(),
+    }
+}
+
+fn file_match() -> GrepFileMatch {
+    GrepFileMatch {
+        path: PathBuf::from("src/lib.rs"),
+        matches: vec![
+            GrepMatch {
+                line_number: 2,
+                context_start: 1,
+                context: "use std::fs;\nfn main() {\n    todo!()".to_string(),
+                match_lines: vec![2],
+                spans: vec![span(2, 3, "main")],
+            },
+            GrepMatch {
+                line_number: 10,
+                context_start: 9,
+                context: "}\nfn main_loop() {\n    main();\n".to_string(),
+                match_lines: vec![10, 11],
+                spans: vec![span(10, 3, "main"), span(10, 8, "loop"), span(11, 4, "main")],
+            },
+        ],
+        binary: false,
+        encoding: None,
+    }
+}
+
+#[test]
+fn test_format_grep_results_text() {
+    let empty = GrepFileMatch { path: PathBuf::from("empty.rs"), matches: vec![], binary: false, encoding: None };
+    let text = format_grep_results(&[file_match(), empty], GrepFormat::Text);
+
+    assert_eq!(
+        text,
+        "3 matches in 1 file\n\
+         \n\
+         src/lib.rs\n\
+         \u{20}  1: use std::fs;\n\
+         >  2:4: fn main() {\n\
+         \u{20}  3:     todo!()\n\
+         \u{20} --\n\
+         \u{20}  9: }\n\
+         > 10:4,9: fn main_loop() {\n\
+         > 11:5:     main();"
+    );
+    assert_eq!(format_grep_results(&[], GrepFormat::Text), "No matches found");
+}
+
+#[test]
+fn test_format_grep_results_json() {
+    let json = format_grep_results(&[file_match()], GrepFormat::Json);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(value[0]["path"], "src/lib.rs");
+    assert_eq!(value[0]["matches"][1]["line_number"], 10);
+    assert_eq!(value[0]["matches"][1]["context_start"], 9);
+    assert_eq!(value[0]["matches"][1]["spans"][1]["text"], "loop");
+}
+
+#[test]
+fn test_format_grep_page() {
+    let page = GrepPage {
+        files: vec![file_match()],
+        total_files: 4,
+        total_matches: 12,
+        capped_files: vec![CappedFile { path: PathBuf::from("src/lib.rs"), total_matches: 7 }],
+        next_cursor: Some("0:2".to_string()),
+        errors: vec![],
+    };
+    let text = format_grep_page(&page, GrepFormat::Text);
+
+    assert!(text.starts_with("3 matches in 1 file (of 12 matches in 4 files)\n\nsrc/lib.rs\n"));
+    assert!(text.contains("\n  [file has 7 matches; only the first 3 are returned]\n"));
+    assert!(text.ends_with("More matches: call grep_codebase again with cursor \"0:2\""));
+
+    let empty = GrepPage { files: vec![], total_files: 0, total_matches: 0, capped_files: vec![], next_cursor: None, errors: vec![] };
+    assert_eq!(format_grep_page(&empty, GrepFormat::Text), "No matches found");
+}
+
+#[test]
+fn test_format_grep_page_binary_encoding_and_errors() {
+    let binary = GrepFileMatch { path: PathBuf::from("logo.png"), matches: vec![], binary: true, encoding: None };
+    let utf16 = GrepFileMatch { encoding: Some("UTF-16LE".to_string()), ..file_match() };
+    let page = GrepPage {
+        files: vec![binary, utf16],
+        total_files: 2,
+        total_matches: 3,
+        capped_files: vec![],
+        next_cursor: None,
+        errors: vec![GrepFileError { path: PathBuf::from("secret.txt"), message: "Permission denied".to_string() }],
+    };
+    let text = format_grep_page(&page, GrepFormat::Text);
+
+    assert!(text.starts_with("3 matches in 2 files\n\nlogo.png\n  binary file matches\n\nsrc/lib.rs (UTF-16LE)\n"));
+    assert!(text.ends_with("\n\nCould not search 1 file:\n  secret.txt: Permission denied"));
+}
+
+#[test]
+fn test_format_replace_result() {
+    let mut result = ReplaceResult {
+        files: vec![FileReplacement {
+            path: PathBuf::from("src/lib.rs"),
+            replacements: 2,
+            hunks: vec![ReplaceHunk {
+                old_start: 4,
+                new_start: 4,
+                old_lines: vec!["old(a);".to_string(), "old(b);".to_string()],
+                new_lines: vec!["new(a);".to_string(), "new(b);".to_string()],
+            }],
+        }],
+        errors: vec![GrepFileError { path: PathBuf::from("legacy.txt"), message: "encoded as windows-1252; only UTF-8 files are replaced".to_string() }],
+        applied: false,
+        written: vec![],
+    };
+    let text = format_replace_result(&result);
+
+    assert!(text.starts_with("Preview of 2 replacements in 1 file; nothing has been written."));
+    assert!(text.contains(
+        "\n\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -4,2 +4,2 @@\n-old(a);\n-old(b);\n+new(a);\n+new(b);\n\nSkipped 1 file:\n  legacy.txt: encoded as"
+    ));
+
+    result.applied = true;
+    assert!(format_replace_result(&result).starts_with("Applied 2 replacements in 1 file, but wrote only 0 files\n\n"));
+    result.written = vec![PathBuf::from("src/lib.rs")];
+    assert!(format_replace_result(&result).starts_with("Applied 2 replacements in 1 file\n\n--- a/src/lib.rs"));
+
+    let empty = ReplaceResult::default();
+    assert_eq!(format_replace_result(&empty), "No matches to replace");
+}
@@ -6,6 +6,8 @@ use tempfile::TempDir;
 
 mod read_tests;
 mod failing_test;
+mod progress_tests;
+mod format_tests;
 
 #[test]
 fn test_new() {
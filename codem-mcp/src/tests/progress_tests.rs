@@ -0,0 +1,51 @@
+use serde_json::json;
+use crate::tools::{progress::Progress, types::ToolCall};
+
+fn tool_call(params: serde_json::Value) -> ToolCall {
+    serde_json::from_value(params).unwrap()
+}
+
+#[test]
+fn test_progress_token_parsed_from_meta() {
+    let call = tool_call(json!({
+        "name": "run_command",
+        "arguments": { "session_id": "abc", "command": "echo hi" },
+        "_meta": { "progressToken": 7 }
+    }));
+    assert_eq!(call.progress_token(), Some(&json!(7)));
+
+    let call = tool_call(json!({
+        "name": "run_command",
+        "arguments": {},
+        "_meta": { "progressToken": "token-1" }
+    }));
+    assert_eq!(call.progress_token(), Some(&json!("token-1")));
+}
+
+#[test]
+fn test_progress_token_missing() {
+    let call = tool_call(json!({
+        "name": "run_command",
+        "arguments": {}
+    }));
+    assert_eq!(call.progress_token(), None);
+
+    let call = tool_call(json!({
+        "name": "run_command",
+        "arguments": {},
+        "_meta": {}
+    }));
+    assert_eq!(call.progress_token(), None);
+}
+
+#[tokio::test]
+async fn test_progress_without_token_has_no_sender() {
+    let call = tool_call(json!({
+        "name": "run_test_command",
+        "arguments": { "session_id": "abc" }
+    }));
+
+    let progress = Progress::start(&call);
+    assert!(progress.sender().is_none());
+    progress.finish().await;
+}
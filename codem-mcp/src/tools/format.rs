@@ -1,4 +1,5 @@
-use codem_core::types::TreeEntry;
+use codem_client::types::{GrepPage, ReplaceResult};
+use codem_core::types::{GrepFileError, GrepFileMatch, TreeEntry};
 
 pub fn format_tree_entry(entry: &TreeEntry, include_stats: bool) -> String {
     let mut output = String::new();
@@ -54,4 +55,205 @@ pub fn format_tree_entry(entry: &TreeEntry, include_stats: bool) -> String {
     }
 
     output
-}
\ No newline at end of file
+}
+
+/// How grep results are returned to the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrepFormat {
+    Text,
+    Json,
+}
+
+impl GrepFormat {
+    /// Parse a tool's `format` argument, defaulting to text
+    pub fn from_arg(format: Option<&str>) -> Self {
+        match format {
+            Some("json") => GrepFormat::Json,
+            _ => GrepFormat::Text,
+        }
+    }
+}
+
+/// Render one file's matches under a path header, one block of line-numbered
+/// context per match, with matching lines marked by `>` and followed by the
+/// 1-based columns of each hit. Binary files just say they match.
+pub fn format_grep_file_match(file_match: &GrepFileMatch) -> String {
+    let mut header = file_match.path.display().to_string();
+    if let Some(encoding) = &file_match.encoding {
+        header.push_str(&format!(" ({})", encoding));
+    }
+    if file_match.binary {
+        return format!("{}\n  binary file matches", header);
+    }
+
+    let width = file_match.matches.iter()
+        .map(|m| m.context_start + m.context.lines().count().saturating_sub(1))
+        .max()
+        .unwrap_or(0)
+        .to_string()
+        .len();
+
+    let blocks = file_match.matches.iter()
+        .map(|m| {
+            m.context.lines()
+                .enumerate()
+                .map(|(i, line)| {
+                    let line_number = m.context_start + i;
+                    if !m.match_lines.contains(&line_number) {
+                        return format!("  {:>width$}: {}", line_number, line, width = width);
+                    }
+                    let columns = m.spans.iter()
+                        .filter(|span| span.line_number == line_number)
+                        .map(|span| (span.char_start + 1).to_string())
+                        .collect::<Vec<_>>();
+                    format!("> {:>width$}:{}: {}", line_number, columns.join(","), line, width = width)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}\n{}", header, blocks.join("\n  --\n"))
+}
+
+/// Render grep results for all files in the requested format
+pub fn format_grep_results(file_matches: &[GrepFileMatch], format: GrepFormat) -> String {
+    let file_matches: Vec<_> = file_matches.iter().filter(|m| m.binary || !m.matches.is_empty()).collect();
+
+    match format {
+        GrepFormat::Json => serde_json::to_string(&file_matches).unwrap_or_default(),
+        GrepFormat::Text if file_matches.is_empty() => "No matches found".to_string(),
+        GrepFormat::Text => {
+            let match_count: usize = file_matches.iter()
+                .flat_map(|m| &m.matches)
+                .map(|m| m.match_lines.len())
+                .sum();
+            let files = file_matches.iter()
+                .map(|m| format_grep_file_match(m))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            format!(
+                "{} in {}\n\n{}",
+                plural(match_count, "match", "matches"),
+                plural(file_matches.len(), "file", "files"),
+                files
+            )
+        }
+    }
+}
+
+/// Render one page of codebase search results, saying how much was left out
+/// and how to get the rest
+pub fn format_grep_page(page: &GrepPage, format: GrepFormat) -> String {
+    if format == GrepFormat::Json {
+        return serde_json::to_string(page).unwrap_or_default();
+    }
+    let errors = format_file_errors("Could not search", &page.errors);
+    if page.files.is_empty() {
+        let text = if page.total_files == 0 { "No matches found" } else { "No more matches" };
+        return format!("{}{}", text, errors);
+    }
+
+    let match_count = page.match_count();
+    let mut header = format!(
+        "{} in {}",
+        plural(match_count, "match", "matches"),
+        plural(page.files.len(), "file", "files")
+    );
+    if match_count < page.total_matches || page.files.len() < page.total_files {
+        header.push_str(&format!(
+            " (of {} in {})",
+            plural(page.total_matches, "match", "matches"),
+            plural(page.total_files, "file", "files")
+        ));
+    }
+
+    let files = page.files.iter()
+        .map(|file_match| {
+            let mut text = format_grep_file_match(file_match);
+            if let Some(capped) = page.capped_files.iter().find(|c| c.path == file_match.path) {
+                text.push_str(&format!(
+                    "\n  [file has {} matches; only the first {} are returned]",
+                    capped.total_matches,
+                    file_match.matches.iter().map(|m| m.match_lines.len()).sum::<usize>()
+                ));
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut text = format!("{}\n\n{}{}", header, files, errors);
+    if let Some(cursor) = &page.next_cursor {
+        text.push_str(&format!("\n\nMore matches: call grep_codebase again with cursor \"{}\"", cursor));
+    }
+    text
+}
+
+/// Render a codebase replacement as a unified diff per file, saying whether
+/// it was applied or only previewed
+pub fn format_replace_result(result: &ReplaceResult) -> String {
+    let errors = format_file_errors("Skipped", &result.errors);
+    if result.files.is_empty() {
+        return format!("No matches to replace{}", errors);
+    }
+
+    let summary = format!(
+        "{} in {}",
+        plural(result.replacements(), "replacement", "replacements"),
+        plural(result.files.len(), "file", "files")
+    );
+    let header = if result.applied && result.written.len() < result.files.len() {
+        format!("Applied {}, but wrote only {}", summary, plural(result.written.len(), "file", "files"))
+    } else if result.applied {
+        format!("Applied {}", summary)
+    } else {
+        format!("Preview of {}; nothing has been written. Call replace_codebase again with apply: true to make these edits.", summary)
+    };
+
+    let diffs = result.files.iter()
+        .map(|file| {
+            let path = file.path.display();
+            let mut diff = format!("--- a/{}\n+++ b/{}", path, path);
+            for hunk in &file.hunks {
+                diff.push_str(&format!(
+                    "\n@@ -{},{} +{},{} @@",
+                    hunk.old_start,
+                    hunk.old_lines.len(),
+                    hunk.new_start,
+                    hunk.new_lines.len()
+                ));
+                for line in &hunk.old_lines {
+                    diff.push_str(&format!("\n-{}", line));
+                }
+                for line in &hunk.new_lines {
+                    diff.push_str(&format!("\n+{}", line));
+                }
+            }
+            diff
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("{}\n\n{}{}", header, diffs, errors)
+}
+
+/// List files that were left out and why, or nothing if there were none
+fn format_file_errors(heading: &str, errors: &[GrepFileError]) -> String {
+    if errors.is_empty() {
+        return String::new();
+    }
+    let lines = errors.iter()
+        .map(|error| format!("  {}: {}", error.path.display(), error.message))
+        .collect::<Vec<_>>();
+    format!(
+        "\n\n{} {}:\n{}",
+        heading,
+        plural(errors.len(), "file", "files"),
+        lines.join("\n")
+    )
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
@@ -1,8 +1,14 @@
 use serde_json::json;
 use jsonrpc_stdio_server::jsonrpc_core::{Result, Value};
 use std::path::PathBuf;
-use crate::{error::format_error_response, server::Mcp};
-use codem_core::types::GrepFileMatch;
+use codem_client::types::GrepPaging;
+use codem_core::types::GrepOptions;
+use crate::{
+    error::format_error_response,
+    server::Mcp,
+    tools::types::path_filter,
+    tools::format::{format_grep_page, format_grep_results, GrepFormat},
+};
 
 pub fn grep_file_schema() -> Value {
     json!({
@@ -30,6 +36,32 @@ pub fn grep_file_schema() -> Value {
                 "description": "Number of context lines to include around matches",
                 "default": 2,
                 "minimum": 0
+            },
+            "fixed_string": {
+                "type": "boolean",
+                "description": "Match the pattern literally instead of as a regex, e.g. to find `foo.bar(`",
+                "default": false
+            },
+            "whole_word": {
+                "type": "boolean",
+                "description": "Only match the pattern at word boundaries",
+                "default": false
+            },
+            "multiline": {
+                "type": "boolean",
+                "description": "Let the pattern match across line breaks, e.g. `fn new\\(\\)\\s*\\{\\n\\s*Self`",
+                "default": false
+            },
+            "binary": {
+                "type": "boolean",
+                "description": "Also search binary files, reporting only whether each one matches; by default they're skipped",
+                "default": false
+            },
+            "format": {
+                "type": "string",
+                "enum": ["text", "json"],
+                "description": "Return matches as line-numbered text, or as JSON with each context block's context_start, context, match_lines and spans (byte and character ranges of every hit)",
+                "default": "text"
             }
         },
         "required": ["session_id", "path", "pattern"]
@@ -52,6 +84,16 @@ pub fn grep_codebase_schema() -> Value {
                 "type": "string",
                 "description": "Optional regex pattern to filter files to search"
             },
+            "include": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; only matching files are included, e.g. [\"src/**/tests/**\"]"
+            },
+            "exclude": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; matching files and directories are skipped, e.g. [\"generated/\"]"
+            },
             "pattern": {
                 "type": "string",
                 "description": "Regex pattern to search for"
@@ -66,43 +108,110 @@ pub fn grep_codebase_schema() -> Value {
                 "description": "Number of context lines to include around matches",
                 "default": 2,
                 "minimum": 0
+            },
+            "fixed_string": {
+                "type": "boolean",
+                "description": "Match the pattern literally instead of as a regex, e.g. to find `foo.bar(`",
+                "default": false
+            },
+            "whole_word": {
+                "type": "boolean",
+                "description": "Only match the pattern at word boundaries",
+                "default": false
+            },
+            "multiline": {
+                "type": "boolean",
+                "description": "Let the pattern match across line breaks, e.g. `fn new\\(\\)\\s*\\{\\n\\s*Self`",
+                "default": false
+            },
+            "binary": {
+                "type": "boolean",
+                "description": "Also search binary files, reporting only whether each one matches; by default they're skipped",
+                "default": false
+            },
+            "max_matches": {
+                "type": "integer",
+                "description": "Most matching lines to return in this page",
+                "default": DEFAULT_MAX_MATCHES,
+                "minimum": 1
+            },
+            "max_files": {
+                "type": "integer",
+                "description": "Most files to return in this page",
+                "minimum": 1
+            },
+            "max_matches_per_file": {
+                "type": "integer",
+                "description": "Most matching lines to return from any one file; the rest of that file's matches are skipped rather than paged",
+                "minimum": 1
+            },
+            "cursor": {
+                "type": "string",
+                "description": "next_cursor from a previous call with the same path, pattern and options, to get the next page. Pages are worked out again on each call, so if files change between calls, matches can be skipped or repeated."
+            },
+            "format": {
+                "type": "string",
+                "enum": ["text", "json"],
+                "description": "Return matches as line-numbered text, or as JSON with the page's files (each context block's context_start, context, match_lines and spans), total_files, total_matches, capped_files and next_cursor",
+                "default": "text"
             }
         },
         "required": ["session_id", "pattern"]
     })
 }
 
+/// Matches returned per page of codebase results unless the caller asks otherwise
+pub const DEFAULT_MAX_MATCHES: usize = 100;
+
+/// Which page of results a grep tool's arguments ask for
+pub fn grep_paging(arguments: &Value) -> GrepPaging {
+    let limit = |name: &str| arguments.get(name).and_then(|v| v.as_u64()).map(|v| v as usize);
+    GrepPaging {
+        max_matches: Some(limit("max_matches").unwrap_or(DEFAULT_MAX_MATCHES)),
+        max_files: limit("max_files"),
+        max_matches_per_file: limit("max_matches_per_file"),
+        cursor: arguments.get("cursor")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+    }
+}
+
+/// Search options from a grep tool's arguments
+pub fn grep_options(arguments: &Value, default_context_lines: usize) -> GrepOptions {
+    let flag = |name: &str| arguments.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+    GrepOptions {
+        file_pattern: arguments.get("file_pattern")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        case_sensitive: flag("case_sensitive"),
+        context_lines: arguments.get("context_lines")
+            .and_then(|v| v.as_u64())
+            .map_or(default_context_lines, |v| v as usize),
+        fixed_string: flag("fixed_string"),
+        whole_word: flag("whole_word"),
+        multiline: flag("multiline"),
+        search_binary: flag("binary"),
+        path_filter: path_filter(arguments),
+    }
+}
+
 pub async fn grep_file(
     mcp: &Mcp, 
     session_id: &str, 
     path: &str, 
     pattern: &str, 
-    case_sensitive: bool, 
-    context_lines: usize
+    options: &GrepOptions,
+    format: GrepFormat,
 ) -> Result<Value> {
-    match mcp.client.grep_file(session_id, PathBuf::from(path), pattern, case_sensitive, context_lines).await {
-        Ok(file_matches) => {
-            let mut content = vec![
-                json!({
+    match mcp.client.grep_file(session_id, PathBuf::from(path), pattern, options).await {
+        Ok(file_matches) => Ok(json!({
+            "content": [
+                {
                     "type": "text",
-                    "text": format!("Search results for pattern '{}' in {}", pattern, path)
-                })
-            ];
-
-            if file_matches.is_empty() {
-                content.push(json!({
-                    "type": "text",
-                    "text": "\nNo matches found"
-                }));
-            } else if let Some(file_match) = file_matches.first() {
-                content.push(json!({
-                    "type": "text", 
-                    "text": format_matches(file_match)
-                }));
-            }
-
-            Ok(json!({ "content": content }))
-        },
+                    "text": format_grep_results(&file_matches, format)
+                }
+            ]
+        })),
         Err(e) => Ok(format_error_response(e.to_string()))
     }
 }
@@ -111,51 +220,21 @@ pub async fn grep_codebase(
     mcp: &Mcp, 
     session_id: &str, 
     path: Option<&str>, 
-    file_pattern: Option<&str>, 
     pattern: &str,
-    case_sensitive: bool, 
-    context_lines: usize
+    options: &GrepOptions,
+    paging: &GrepPaging,
+    format: GrepFormat,
 ) -> Result<Value> {
     let path = path.map(PathBuf::from);
-    match mcp.client.grep_codebase(session_id, path.as_deref(), file_pattern, pattern, case_sensitive, context_lines).await {
-        Ok(file_matches) => {
-            let mut content = vec![];
-
-            if file_matches.is_empty() {
-                content.push(json!({
+    match mcp.client.grep_codebase_page(session_id, path.as_deref(), pattern, options, paging).await {
+        Ok(page) => Ok(json!({
+            "content": [
+                {
                     "type": "text",
-                    "text": "\nNo matches found in any files"
-                }));
-            } else {
-                for file_match in file_matches {
-                    if !file_match.matches.is_empty() {
-                        content.push(json!({
-                            "type": "text",
-                            "text": format_matches(&file_match)
-                        }));
-                    }
+                    "text": format_grep_page(&page, format)
                 }
-            }
-
-            Ok(json!({ "content": content }))
-        },
+            ]
+        })),
         Err(e) => Ok(format_error_response(e.to_string()))
     }
 }
-
-fn format_matches(file_match: &GrepFileMatch) -> String {
-    let path_display = file_match.path.display();
-    if file_match.matches.is_empty() {
-        return format!("\nIn file {}: No matches found", path_display);
-    }
-
-    let matches_text = file_match.matches
-        .iter()
-        .map(|m| {
-            format!("Match on line {}:\n{}", m.line_number, m.context)
-        })
-        .collect::<Vec<_>>()
-        .join("\n\n");
-
-    format!("\nIn file {}:\n{}", path_display, matches_text)
-}
\ No newline at end of file
@@ -7,6 +7,8 @@ use crate::tools::{
     handler_grep,
     handler_write_small,
     handler_command,
+    handler_job,
+    handler_history,
 };
 use crate::tools::types::ToolCall;
 
@@ -17,13 +19,22 @@ pub async fn handle_tool_call(mcp: &Mcp, call: ToolCall) -> Result<Value> {
         "list_directory" => handler_read::handle_list_directory(mcp, &call).await,
         "grep_file" => handler_grep::handle_grep_file(mcp, &call).await,
         "grep_codebase" => handler_grep::handle_grep_codebase(mcp, &call).await,
+        "replace_codebase" => handler_write::handle_replace_codebase(mcp, &call).await,
         "create_new_file" => handler_write::handle_write_new_file(mcp, &call).await,
         "write_file_full" => handler_write::handle_write_file_full(mcp, &call).await,
         "write_file_small" => handler_write_small::handle_write_file_small(mcp, &call).await,
         "write_file_large" => handler_write::handle_write_file_large(mcp, &call).await,
         "run_command" => handler_command::handle_run_command(mcp, &call).await,
         "run_command_risky" => handler_command::handle_run_command_risky(mcp, &call).await,
+        "await_approval" => handler_command::handle_await_approval(mcp, &call).await,
         "run_test_command" => handler_command::handle_run_test_command(mcp, &call).await,
+        "run_checks" => handler_command::handle_run_checks(mcp, &call).await,
+        "start_job" => handler_job::handle_start_job(mcp, &call, false).await,
+        "start_job_risky" => handler_job::handle_start_job(mcp, &call, true).await,
+        "job_status" => handler_job::handle_job_status(mcp, &call).await,
+        "tail_job" => handler_job::handle_tail_job(mcp, &call).await,
+        "kill_job" => handler_job::handle_kill_job(mcp, &call).await,
+        "command_history" => handler_history::handle_command_history(mcp, &call).await,
         _ => Ok(crate::error::format_error_response(format!("Unknown tool: {}", call.name)))
     }
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde_json::json;
 use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
-use crate::{server::Mcp, tools::types::ToolCall};
+use codem_client::{error::ClientError, types::{cached_note, CommandResult, TestReport}};
+use crate::{server::Mcp, tools::{progress::Progress, types::ToolCall}};
 
 pub async fn handle_run_command(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
     let session_id = call.arguments.get("session_id")
@@ -19,22 +20,29 @@ pub async fn handle_run_command(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
     let timeout = call.arguments.get("timeout")
         .and_then(|v| v.as_u64());
 
-    match mcp.client.run_command(session_id, command, cwd, timeout).await {
-        Ok(output) => Ok(json!({
+    let stdin = call.arguments.get("stdin")
+        .and_then(|v| v.as_str());
+
+    let progress = Progress::start(call);
+    let result = mcp.client.run_command(session_id, command, cwd, timeout, stdin, progress.sender()).await;
+    progress.finish().await;
+
+    match result {
+        Ok(result) => Ok(json!({
             "content": [
                 {
                     "type": "text",
-                    "text": output
+                    "text": command_output_text(result)
                 }
             ]
         })),
         Err(err) => {
-            if matches!(err, codem_client::error::ClientError::UnsafeCommand { .. }) {
+            if let codem_client::error::ClientError::UnsafeCommand { reason, .. } = &err {
                 Ok(json!({
                     "content": [
                         {
                             "type": "text", 
-                            "text": format!("Command '{}' is not marked as safe. Use run_command_risky if you want to run this command.", command)
+                            "text": format!("Command '{}' is not marked as safe: {}. Use run_command_risky if you want to run this command.", command, reason)
                         }
                     ]
                 }))
@@ -43,7 +51,7 @@ pub async fn handle_run_command(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
                     "content": [
                         {
                             "type": "text",
-                            "text": format!("Command failed: {}", err)
+                            "text": command_error_text(err)
                         }
                     ]
                 }))
@@ -68,60 +76,193 @@ pub async fn handle_run_command_risky(mcp: &Mcp, call: &ToolCall) -> Result<Valu
     let timeout = call.arguments.get("timeout")
         .and_then(|v| v.as_u64());
 
-    match mcp.client.run_command_risky(session_id, command, cwd, timeout).await {
-        Ok(output) => Ok(json!({
+    let stdin = call.arguments.get("stdin")
+        .and_then(|v| v.as_str());
+
+    let progress = Progress::start(call);
+    let result = mcp.client.run_command_risky(session_id, command, cwd, timeout, stdin, progress.sender()).await;
+    progress.finish().await;
+
+    Ok(risky_command_response(result))
+}
+
+pub async fn handle_await_approval(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = call.arguments.get("session_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing session_id parameter"))?;
+
+    let approval_id = call.arguments.get("approval_id")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| Error::invalid_params("missing approval_id parameter"))?;
+
+    let progress = Progress::start(call);
+    let result = mcp.client.await_approval(session_id, approval_id, progress.sender()).await;
+    progress.finish().await;
+
+    Ok(risky_command_response(result))
+}
+
+/// A command's output, followed by the session's files it changed
+fn command_output_text(result: CommandResult) -> String {
+    if result.changed_files.is_empty() {
+        return result.output;
+    }
+    with_changed_files(result.output, &result.changed_files, result.timestamps_refreshed)
+}
+
+/// Why a command failed, followed by the session's files it changed before that
+fn command_error_text(err: ClientError) -> String {
+    match err {
+        ClientError::CommandFailedWithChanges { error, changed_files, timestamps_refreshed } => {
+            with_changed_files(format!("Command failed: {}", error), &changed_files, timestamps_refreshed)
+        }
+        err => format!("Command failed: {}", err),
+    }
+}
+
+fn with_changed_files(text: String, changed_files: &[PathBuf], timestamps_refreshed: bool) -> String {
+    let files: Vec<_> = changed_files.iter().map(|path| path.display().to_string()).collect();
+    let note = if timestamps_refreshed {
+        "Their timestamps were refreshed, so they can be written without reading them again."
+    } else {
+        "Read them again before writing to them."
+    };
+    format!("{}\n\nFiles changed by the command: {}\n{}", text, files.join(", "), note)
+}
+
+/// Response for a risky command, which may be waiting on or refused by a human reviewer
+fn risky_command_response(result: std::result::Result<CommandResult, ClientError>) -> Value {
+    let text = match result {
+        Ok(result) => command_output_text(result),
+        Err(ClientError::ApprovalPending { id }) => format!(
+            "Command is waiting for a human to approve it (approval_id {}). Call await_approval with this approval_id to keep waiting and get its output once approved.",
+            id
+        ),
+        Err(err @ ClientError::CommandDenied { .. }) => format!("{}. Do not retry it without asking the user.", err),
+        Err(err) => command_error_text(err),
+    };
+    json!({
+        "content": [
+            {
+                "type": "text",
+                "text": text
+            }
+        ]
+    })
+}
+
+/// Text result of a test run, followed by the parsed results as JSON if there are any
+fn test_run_response(text: String, report: Option<&TestReport>) -> Value {
+    let mut content = vec![json!({
+        "type": "text",
+        "text": text
+    })];
+    if let Some(report) = report {
+        content.push(json!({
+            "type": "text",
+            "text": serde_json::to_string(report).unwrap_or_default()
+        }));
+    }
+    json!({ "content": content })
+}
+
+pub async fn handle_run_test_command(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = call.arguments.get("session_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing session_id parameter"))?;
+
+    let affected_only = call.arguments.get("affected_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let no_cache = call.arguments.get("no_cache")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let progress = Progress::start(call);
+    let (selection, result) = if affected_only {
+        match mcp.client.select_affected_tests(session_id).await {
+            Ok(selection) => {
+                let result = mcp.client.run_test_selection(session_id, &selection, no_cache, progress.sender()).await;
+                (Some(selection), result)
+            }
+            Err(err) => (None, Err(err)),
+        }
+    } else {
+        (None, mcp.client.run_test_command(session_id, no_cache, progress.sender()).await)
+    };
+    progress.finish().await;
+
+    // A cached run's summary is marked already; failures are marked here
+    let cached_at = match &result {
+        Err(ClientError::TestCommandFailed { cached_at, .. }) => *cached_at,
+        _ => None,
+    };
+
+    // Say which tests were chosen before their results
+    let with_selection = |text: String| {
+        let text = match cached_at {
+            Some(ran_at) => format!("{}\n{}", cached_note(ran_at), text),
+            None => text,
+        };
+        match &selection {
+            Some(selection) => format!("{}\n\n{}", selection, text),
+            None => text,
+        }
+    };
+
+    match result {
+        Ok(run) => Ok(test_run_response(with_selection(run.summary()), run.report.as_ref())),
+        Err(ClientError::TestCommandNotConfigured) => Ok(json!({
             "content": [
                 {
-                    "type": "text",
-                    "text": output
+                    "type": "text", 
+                    "text": "No test command configured for this project."
                 }
             ]
         })),
+        // Failed tests are summarised instead of returning all of the output
+        Err(ClientError::TestCommandFailed { exit_code, report: Some(report), .. }) => Ok(test_run_response(
+            with_selection(format!("Test command failed (exit code {}):\n{}", exit_code, report)),
+            Some(&report),
+        )),
         Err(err) => Ok(json!({
             "content": [
                 {
                     "type": "text",
-                    "text": format!("Command failed: {}", err)
+                    "text": with_selection(format!("Test command failed: {}", err))
                 }
             ]
-        }))
+        })),
     }
 }
 
-pub async fn handle_run_test_command(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+pub async fn handle_run_checks(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
     let session_id = call.arguments.get("session_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| Error::invalid_params("missing session_id parameter"))?;
 
-    match mcp.client.run_test_command(session_id).await {
-        Ok(output) => Ok(json!({
-            "content": [
-                {
-                    "type": "text",
-                    "text": output
-                }
-            ]
-        })),
-        Err(err) => {
-            if matches!(err, codem_client::error::ClientError::TestCommandNotConfigured) {
-                Ok(json!({
-                    "content": [
-                        {
-                            "type": "text", 
-                            "text": "No test command configured for this project."
-                        }
-                    ]
-                }))
-            } else {
-                Ok(json!({
-                    "content": [
-                        {
-                            "type": "text",
-                            "text": format!("Test command failed: {}", err)
-                        }
-                    ]
-                }))
+    let stages: Option<Vec<String>> = call.arguments.get("stages")
+        .and_then(|v| v.as_array())
+        .map(|stages| stages.iter().filter_map(|s| s.as_str().map(String::from)).collect());
+
+    let stop_on_failure = call.arguments.get("stop_on_failure")
+        .and_then(|v| v.as_bool());
+
+    let progress = Progress::start(call);
+    let result = mcp.client.run_checks(session_id, stages.as_deref(), stop_on_failure, progress.sender()).await;
+    progress.finish().await;
+
+    let text = match result {
+        Ok(report) => report.to_string(),
+        Err(err) => format!("Could not run checks: {}", err),
+    };
+    Ok(json!({
+        "content": [
+            {
+                "type": "text",
+                "text": text
             }
-        }
-    }
-}
\ No newline at end of file
+        ]
+    }))
+}
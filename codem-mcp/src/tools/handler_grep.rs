@@ -1,5 +1,5 @@
 use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
-use crate::{server::Mcp, tools::grep};
+use crate::{server::Mcp, tools::{format::GrepFormat, grep}};
 use crate::tools::types::ToolCall;
 
 pub async fn handle_grep_file(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
@@ -15,15 +15,11 @@ pub async fn handle_grep_file(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
         .and_then(|v| v.as_str())
         .ok_or_else(|| Error::invalid_params("missing pattern parameter"))?;
 
-    let case_sensitive = call.arguments.get("case_sensitive")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+    let options = grep::grep_options(&call.arguments, 0);
 
-    let context_lines = call.arguments.get("context_lines")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
+    let format = GrepFormat::from_arg(call.arguments.get("format").and_then(|v| v.as_str()));
 
-    grep::grep_file(mcp, session_id, path, pattern, case_sensitive, context_lines).await
+    grep::grep_file(mcp, session_id, path, pattern, &options, format).await
 }
 
 pub async fn handle_grep_codebase(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
@@ -38,16 +34,11 @@ pub async fn handle_grep_codebase(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
     let path = call.arguments.get("path")
         .and_then(|v| v.as_str());
 
-    let file_pattern = call.arguments.get("file_pattern")
-        .and_then(|v| v.as_str());
+    let options = grep::grep_options(&call.arguments, 0);
 
-    let case_sensitive = call.arguments.get("case_sensitive")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+    let format = GrepFormat::from_arg(call.arguments.get("format").and_then(|v| v.as_str()));
 
-    let context_lines = call.arguments.get("context_lines")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
+    let paging = grep::grep_paging(&call.arguments);
 
-    grep::grep_codebase(mcp, session_id, path, file_pattern, pattern, case_sensitive, context_lines).await
+    grep::grep_codebase(mcp, session_id, path, pattern, &options, &paging, format).await
 }
\ No newline at end of file
@@ -0,0 +1,86 @@
+use serde_json::json;
+use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
+use codem_client::types::CommandRun;
+use crate::{server::Mcp, error::format_error_response, tools::types::ToolCall};
+
+/// Bytes of a run's output returned per call
+const OUTPUT_PAGE_BYTES: usize = 20_000;
+
+fn format_run(run: &CommandRun) -> String {
+    let status = match (&run.error, run.exit_code) {
+        (Some(error), _) => error.clone(),
+        (None, Some(exit_code)) => format!("exit code {}", exit_code),
+        (None, None) => "no exit code".to_string(),
+    };
+    let status = match run.cached_at {
+        Some(_) => format!("{}, cached", status),
+        None => status,
+    };
+    format!(
+        "#{} {} [{}, {:.2}s, {} bytes of output] {} (in {})",
+        run.id,
+        run.kind,
+        status,
+        run.duration_ms as f64 / 1000.0,
+        run.output.len(),
+        run.command,
+        run.cwd.display()
+    )
+}
+
+fn text_response(text: String) -> Value {
+    json!({
+        "content": [
+            {
+                "type": "text",
+                "text": text
+            }
+        ]
+    })
+}
+
+pub async fn handle_command_history(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = call.arguments.get("session_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing session_id parameter"))?;
+
+    let Some(run_id) = call.arguments.get("run_id").and_then(|v| v.as_u64()) else {
+        return match mcp.client.command_history(session_id).await {
+            Ok(runs) if runs.is_empty() => Ok(text_response("No commands have been run in this session".to_string())),
+            Ok(runs) => Ok(text_response(
+                runs.iter().map(format_run).collect::<Vec<_>>().join("\n")
+            )),
+            Err(err) => Ok(format_error_response(err.to_string())),
+        };
+    };
+
+    let offset = call.arguments.get("offset")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+
+    let run = match mcp.client.command_run(session_id, run_id).await {
+        Ok(run) => run,
+        Err(err) => return Ok(format_error_response(err.to_string())),
+    };
+
+    let mut start = offset.min(run.output.len());
+    while !run.output.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + OUTPUT_PAGE_BYTES).min(run.output.len());
+    while !run.output.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let mut text = format_run(&run);
+    if run.output_truncated {
+        text.push_str("\n[output was truncated before it was stored]");
+    }
+    if end < run.output.len() {
+        text.push_str(&format!("\n[showing bytes {}-{}; call again with offset {} for more]", start, end, end));
+    }
+    text.push('\n');
+    text.push_str(&run.output[start..end]);
+
+    Ok(text_response(text))
+}
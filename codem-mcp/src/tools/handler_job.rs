@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::time::SystemTime;
+use serde_json::json;
+use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
+use codem_client::{error::ClientError, types::JobInfo};
+use crate::{server::Mcp, error::format_error_response, tools::types::ToolCall};
+
+fn get_session_id(call: &ToolCall) -> Result<&str> {
+    call.arguments.get("session_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing session_id parameter"))
+}
+
+fn get_job_id(call: &ToolCall) -> Result<&str> {
+    call.arguments.get("job_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing job_id parameter"))
+}
+
+fn format_job(job: &JobInfo) -> String {
+    let end = job.finished_at.unwrap_or_else(SystemTime::now);
+    let elapsed = end.duration_since(job.started_at).unwrap_or_default();
+    format!(
+        "{} [{}, {}s, {} bytes of output] {}",
+        job.id, job.status, elapsed.as_secs(), job.output_len, job.command
+    )
+}
+
+fn text_response(text: String) -> Value {
+    json!({
+        "content": [
+            {
+                "type": "text",
+                "text": text
+            }
+        ]
+    })
+}
+
+pub async fn handle_start_job(mcp: &Mcp, call: &ToolCall, risky: bool) -> Result<Value> {
+    let session_id = get_session_id(call)?;
+
+    let command = call.arguments.get("command")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing command parameter"))?;
+
+    let cwd = call.arguments.get("cwd")
+        .and_then(|v| v.as_str())
+        .map(Path::new);
+
+    let result = if risky {
+        mcp.client.start_job_risky(session_id, command, cwd).await
+    } else {
+        mcp.client.start_job(session_id, command, cwd).await
+    };
+
+    match result {
+        Ok(job) => Ok(text_response(format!(
+            "Started {}. Use job_status, tail_job and kill_job with this job_id.\n{}",
+            job.id, format_job(&job)
+        ))),
+        Err(ClientError::UnsafeCommand { reason, .. }) => Ok(format_error_response(format!(
+            "Command '{}' is not marked as safe: {}. Use start_job_risky if you want to run this command.",
+            command, reason
+        ))),
+        Err(err) => Ok(format_error_response(format!("Failed to start job: {}", err))),
+    }
+}
+
+pub async fn handle_job_status(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = get_session_id(call)?;
+
+    let job_id = call.arguments.get("job_id")
+        .and_then(|v| v.as_str());
+
+    let result = match job_id {
+        Some(job_id) => mcp.client.job_status(session_id, job_id).await.map(|job| vec![job]),
+        None => mcp.client.list_jobs(session_id).await,
+    };
+
+    match result {
+        Ok(jobs) if jobs.is_empty() => Ok(text_response("No jobs in this session".to_string())),
+        Ok(jobs) => Ok(text_response(
+            jobs.iter().map(format_job).collect::<Vec<_>>().join("\n")
+        )),
+        Err(err) => Ok(format_error_response(err.to_string())),
+    }
+}
+
+pub async fn handle_tail_job(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = get_session_id(call)?;
+    let job_id = get_job_id(call)?;
+
+    let offset = call.arguments.get("offset")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize);
+
+    match mcp.client.tail_job(session_id, job_id, offset).await {
+        Ok(tail) => {
+            let mut output = if tail.output.is_empty() {
+                "(no new output)".to_string()
+            } else {
+                tail.output
+            };
+            if tail.skipped > 0 {
+                output = format!("[{} bytes of earlier output were discarded]\n{}", tail.skipped, output);
+            }
+            Ok(text_response(format!(
+                "{} is {} (next offset {})\n{}",
+                job_id, tail.status, tail.offset, output
+            )))
+        }
+        Err(err) => Ok(format_error_response(err.to_string())),
+    }
+}
+
+pub async fn handle_kill_job(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = get_session_id(call)?;
+    let job_id = get_job_id(call)?;
+
+    match mcp.client.kill_job(session_id, job_id).await {
+        Ok(job) => Ok(text_response(format_job(&job))),
+        Err(err) => Ok(format_error_response(err.to_string())),
+    }
+}
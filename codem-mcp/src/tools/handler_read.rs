@@ -1,6 +1,6 @@
 use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
 use crate::{server::Mcp, tools::{read, list}};
-use crate::tools::types::ToolCall;
+use crate::tools::types::{path_filter, ToolCall};
 use codem_core::types::ListOptions;
 
 pub async fn handle_read_files(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
@@ -34,6 +34,7 @@ pub async fn handle_list_directory(mcp: &Mcp, call: &ToolCall) -> Result<Value>
         include_size: call.arguments.get("include_size").and_then(|v| v.as_bool()).unwrap_or(false),
         file_pattern: call.arguments.get("file_pattern").and_then(|v| v.as_str()).map(String::from),
         include_modified: false,
+        path_filter: path_filter(&call.arguments),
     };
             
     list::list_directory(mcp, session_id, path, options).await
@@ -1,5 +1,5 @@
 use jsonrpc_stdio_server::jsonrpc_core::{Value, Result, Error};
-use crate::{server::Mcp, tools::write};
+use crate::{server::Mcp, tools::{grep, replace, write}};
 use crate::tools::types::ToolCall;
 
 pub async fn handle_write_new_file(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
@@ -68,4 +68,29 @@ pub async fn handle_write_file_large(mcp: &Mcp, call: &ToolCall) -> Result<Value
         .unwrap_or(false);
 
     write::write_file_large(mcp, session_id, path, start_str, end_str, new_str, run_test).await
-}
\ No newline at end of file
+}
+
+pub async fn handle_replace_codebase(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
+    let session_id = call.arguments.get("session_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing session_id parameter"))?;
+
+    let pattern = call.arguments.get("pattern")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing pattern parameter"))?;
+
+    let replacement = call.arguments.get("replacement")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::invalid_params("missing replacement parameter"))?;
+
+    let path = call.arguments.get("path")
+        .and_then(|v| v.as_str());
+
+    let apply = call.arguments.get("apply")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let options = grep::grep_options(&call.arguments, 0);
+
+    replace::replace_codebase(mcp, session_id, path, pattern, replacement, &options, apply).await
+}
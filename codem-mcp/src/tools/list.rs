@@ -37,6 +37,16 @@ pub fn list_directory_schema() -> Value {
                 "type": "string",
                 "description": "Optional regex to filter filenames (note that this does not imply recursive; you still need to set recursive to true)",
                 "optional": true
+            },
+            "include": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; only matching files are included, e.g. [\"src/**/tests/**\"]"
+            },
+            "exclude": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; matching files and directories are skipped, e.g. [\"generated/\"]"
             }
         },
         "required": ["session_id"]
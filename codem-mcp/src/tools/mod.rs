@@ -3,6 +3,7 @@ pub mod format;
 pub mod list;
 pub mod read;
 pub mod grep;
+pub mod replace;
 pub mod write;
 pub mod types;
 pub mod tools_list;
@@ -12,6 +13,9 @@ pub mod handler_grep;
 pub mod handler_read;
 pub mod handler_write_small;
 pub mod handler_command;
+pub mod handler_job;
+pub mod handler_history;
+pub mod progress;
 
 // Export the key types and functions
 pub use types::ToolCall;
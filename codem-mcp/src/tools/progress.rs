@@ -0,0 +1,79 @@
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, sync::mpsc, task::JoinHandle};
+use codem_core::types::{OutputChunk, OutputSender};
+use crate::tools::types::ToolCall;
+
+/// Forwards command output to the MCP client as `notifications/progress`
+/// while a tool call is still running.
+///
+/// Only active if the tool call carried a `progressToken`; otherwise
+/// `sender()` returns `None` and output is only returned in the final result.
+pub struct Progress {
+    sender: Option<OutputSender>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    pub fn start(call: &ToolCall) -> Self {
+        let Some(token) = call.progress_token().cloned() else {
+            return Self { sender: None, task: None };
+        };
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(forward_output(token, receiver));
+
+        Self {
+            sender: Some(sender),
+            task: Some(task),
+        }
+    }
+
+    pub fn sender(&self) -> Option<OutputSender> {
+        self.sender.clone()
+    }
+
+    /// Wait until all pending output has been sent, so no notification
+    /// arrives after the tool call response.
+    pub async fn finish(mut self) {
+        self.sender.take();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+async fn forward_output(token: Value, mut receiver: mpsc::UnboundedReceiver<OutputChunk>) {
+    let mut progress = 0u64;
+
+    while let Some(chunk) = receiver.recv().await {
+        // Batch whatever else is already queued into a single notification
+        let mut message = chunk.text;
+        while let Ok(chunk) = receiver.try_recv() {
+            message.push_str(&chunk.text);
+        }
+
+        progress += 1;
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {
+                "progressToken": token,
+                "progress": progress,
+                "message": message
+            }
+        });
+
+        if let Err(e) = write_notification(&notification).await {
+            tracing::warn!("Failed to send progress notification: {}", e);
+        }
+    }
+}
+
+async fn write_notification(notification: &Value) -> std::io::Result<()> {
+    let mut line = notification.to_string();
+    line.push('\n');
+
+    let mut stdout = tokio::io::stdout();
+    stdout.write_all(line.as_bytes()).await?;
+    stdout.flush().await
+}
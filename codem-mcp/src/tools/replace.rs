@@ -0,0 +1,96 @@
+use serde_json::json;
+use jsonrpc_stdio_server::jsonrpc_core::{Result, Value};
+use std::path::PathBuf;
+use codem_core::types::GrepOptions;
+use crate::{
+    error::format_error_response,
+    server::Mcp,
+    tools::format::format_replace_result,
+};
+
+pub fn replace_codebase_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "path": {
+                "type": "string",
+                "description": "Directory to replace in (relative to project root, defaults to the whole project)"
+            },
+            "pattern": {
+                "type": "string",
+                "description": "Regex pattern to replace"
+            },
+            "replacement": {
+                "type": "string",
+                "description": "Text to replace each match with; refer to capture groups as $1 or ${name}, and write $$ for a literal $"
+            },
+            "apply": {
+                "type": "boolean",
+                "description": "Make the edits. Without it the edits are only previewed as a diff; apply them by calling again with the same arguments and apply: true. Applying fails if a file changed since the preview.",
+                "default": false
+            },
+            "file_pattern": {
+                "type": "string",
+                "description": "Optional glob to filter file names"
+            },
+            "include": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; only matching files are edited"
+            },
+            "exclude": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Gitignore-style globs matched against project-relative paths; matching files and directories are skipped"
+            },
+            "case_sensitive": {
+                "type": "boolean",
+                "description": "Whether to perform case-sensitive matching",
+                "default": false
+            },
+            "fixed_string": {
+                "type": "boolean",
+                "description": "Match the pattern and insert the replacement literally, without regex syntax or capture groups",
+                "default": false
+            },
+            "whole_word": {
+                "type": "boolean",
+                "description": "Only match the pattern at word boundaries",
+                "default": false
+            },
+            "multiline": {
+                "type": "boolean",
+                "description": "Let the pattern match across line breaks",
+                "default": false
+            }
+        },
+        "required": ["session_id", "pattern", "replacement"]
+    })
+}
+
+pub async fn replace_codebase(
+    mcp: &Mcp,
+    session_id: &str,
+    path: Option<&str>,
+    pattern: &str,
+    replacement: &str,
+    options: &GrepOptions,
+    apply: bool,
+) -> Result<Value> {
+    let path = path.map(PathBuf::from);
+    match mcp.client.replace_codebase(session_id, path.as_deref(), pattern, replacement, options, apply).await {
+        Ok(result) => Ok(json!({
+            "content": [
+                {
+                    "type": "text",
+                    "text": format_replace_result(&result)
+                }
+            ]
+        })),
+        Err(e) => Ok(format_error_response(e.to_string()))
+    }
+}
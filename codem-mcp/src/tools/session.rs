@@ -1,8 +1,8 @@
 use serde_json::json;
 use jsonrpc_stdio_server::jsonrpc_core::{Result, Value};
-use codem_core::types::{ListOptions, GrepOptions};
-use crate::{server::Mcp, error::format_error_response, tools::format::format_tree_entry};
-use crate::tools::types::ToolCall;
+use codem_core::types::ListOptions;
+use crate::{server::Mcp, error::format_error_response, tools::{format::{format_grep_page, format_tree_entry, GrepFormat}, grep::{grep_options, grep_paging, DEFAULT_MAX_MATCHES}}};
+use crate::tools::types::{path_filter, ToolCall};
 
 pub fn create_session_schema() -> Value {
     json!({
@@ -29,6 +29,16 @@ pub fn create_session_schema() -> Value {
                     "file_pattern": {
                         "type": "string",
                         "description": "Optional regex to filter filenames"
+                    },
+                    "include": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Gitignore-style globs of project-relative paths to include"
+                    },
+                    "exclude": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Gitignore-style globs of project-relative paths to skip"
                     }
                 }
             },
@@ -54,6 +64,48 @@ pub fn create_session_schema() -> Value {
                     "file_pattern": {
                         "type": "string",
                         "description": "Optional regex to filter files to search"
+                    },
+                    "include": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Gitignore-style globs of project-relative paths to include"
+                    },
+                    "exclude": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Gitignore-style globs of project-relative paths to skip"
+                    },
+                    "fixed_string": {
+                        "type": "boolean",
+                        "description": "Match the pattern literally instead of as a regex",
+                        "default": false
+                    },
+                    "whole_word": {
+                        "type": "boolean",
+                        "description": "Only match the pattern at word boundaries",
+                        "default": false
+                    },
+                    "multiline": {
+                        "type": "boolean",
+                        "description": "Let the pattern match across line breaks",
+                        "default": false
+                    },
+                    "binary": {
+                        "type": "boolean",
+                        "description": "Also search binary files, reporting only whether each one matches",
+                        "default": false
+                    },
+                    "max_matches": {
+                        "type": "integer",
+                        "description": "Most matching lines to return; use grep_codebase with the returned cursor and the same options for more, passing context_lines as well since its default differs",
+                        "default": DEFAULT_MAX_MATCHES,
+                        "minimum": 1
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Return matches as line-numbered text or as JSON",
+                        "default": "text"
                     }
                 },
                 "required": ["pattern"]
@@ -83,10 +135,10 @@ pub async fn create_session(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
             // Run test command if requested
             if let Some(run_test) = call.arguments.get("run_test").and_then(|v| v.as_bool()) {
                 if run_test {
-                    if let Ok(test_result) = mcp.client.run_test_command(&session_id).await {
+                    if let Ok(test_result) = mcp.client.run_test_command(&session_id, false, None).await {
                         content.push(json!({
                             "type": "text",
-                            "text": format!("Test command result: {}", test_result)
+                            "text": format!("Test command result: {}", test_result.summary())
                         }));
                     }
                 }
@@ -105,6 +157,7 @@ pub async fn create_session(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
                     let options = ListOptions {
                         recursive,
                         file_pattern,
+                        path_filter: path_filter(list_dir),
                         ..Default::default()
                     };
 
@@ -122,36 +175,22 @@ pub async fn create_session(mcp: &Mcp, call: &ToolCall) -> Result<Value> {
             }
 
             // Grep codebase if requested
-            if let Some(grep_obj) = call.arguments.get("grep_pattern").and_then(|v| v.as_object()) {
-                let pattern = grep_obj.get("pattern")
+            if let Some(grep_value) = call.arguments.get("grep_pattern").filter(|v| v.is_object()) {
+                let pattern = grep_value.get("pattern")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| jsonrpc_stdio_server::jsonrpc_core::Error::invalid_params("missing grep pattern"))?
                     .to_string();
                     
-                let options = GrepOptions {
-                    case_sensitive: grep_obj.get("case_sensitive")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false),
-                    context_lines: grep_obj.get("context_lines")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as usize)
-                        .unwrap_or(2),
-                    file_pattern: grep_obj.get("file_pattern")
-                        .and_then(|v| v.as_str())
-                        .map(String::from),
-                };
+                let options = grep_options(grep_value, 2);
+
+                let format = GrepFormat::from_arg(grep_value.get("format").and_then(|v| v.as_str()));
+
+                let paging = grep_paging(grep_value);
 
-                if let Ok(grep_result) = mcp.client.grep_codebase(
-                    &session_id,
-                    None,
-                    None,
-                    &pattern,
-                    options.case_sensitive,
-                    options.context_lines
-                ).await {
+                if let Ok(page) = mcp.client.grep_codebase_page(&session_id, None, &pattern, &options, &paging).await {
                     content.push(json!({
                         "type": "text",
-                        "text": format!("Grep results:\n{:#?}", grep_result)
+                        "text": format!("Grep results:\n{}", format_grep_page(&page, format))
                     }));
                 }
             }
@@ -1,6 +1,6 @@
 use serde_json::json;
 use jsonrpc_stdio_server::jsonrpc_core::Value;
-use crate::tools::{session, read, list, grep, write};
+use crate::tools::{session, read, list, grep, replace, write};
 
 pub fn list_tools() -> Value {
     json!({
@@ -30,6 +30,11 @@ pub fn list_tools() -> Value {
                 "description": "Search for a pattern across multiple files",
                 "inputSchema": grep::grep_codebase_schema()
             },
+            {
+                "name": "replace_codebase",
+                "description": "Replace a regex pattern across the codebase, e.g. to rename something. The first call returns a diff preview of every edit without writing anything; call again with apply: true to make the edits. Files don't need to be read first, and previewing doesn't count as reading them for other writes.",
+                "inputSchema": replace::replace_codebase_schema()
+            },
             {
                 "name": "create_new_file",
                 "description": "Create a new file with the specified content",
@@ -57,13 +62,53 @@ pub fn list_tools() -> Value {
             },
             {
                 "name": "run_command_risky",
-                "description": "Run a potentially unsafe command in the project directory",
+                "description": "Run a potentially unsafe command in the project directory. If the project requires approval, the command waits for a human to approve it and may come back with an approval_id to pass to await_approval.",
                 "inputSchema": command_schema()
             },
+            {
+                "name": "await_approval",
+                "description": "Keep waiting for a human to approve a command queued by run_command_risky, and return its output once it has been approved and run",
+                "inputSchema": await_approval_schema()
+            },
             {
                 "name": "run_test_command",
-                "description": "Run the test command configured for the project. You should always test after you finish making a change to the codebase.",
-                "inputSchema": session_only_schema()
+                "description": "Run the test command configured for the project. You should always test after you finish making a change to the codebase. If the output is recognised (cargo test, nextest, pytest, JUnit XML or go test -json), returns a summary of the results with failure messages, followed by the results as JSON. With affected_only, only the tests affected by the files written in this session are run, falling back to the full suite when they can't be determined. If no project files have changed since the same tests last ran, the previous result is returned, marked as cached; pass no_cache to run them anyway.",
+                "inputSchema": run_test_command_schema()
+            },
+            {
+                "name": "run_checks",
+                "description": "Run the check stages configured for the project (e.g. format, check, lint, test) in order. Each stage is reported with its status and output.",
+                "inputSchema": run_checks_schema()
+            },
+            {
+                "name": "start_job",
+                "description": "Start a safe command in the background and return a job_id immediately. Use this for dev servers, watchers and long test suites instead of run_command.",
+                "inputSchema": start_job_schema()
+            },
+            {
+                "name": "start_job_risky",
+                "description": "Start a potentially unsafe command in the background and return a job_id immediately. Not available in projects that require approval for risky commands; use run_command_risky there.",
+                "inputSchema": start_job_schema()
+            },
+            {
+                "name": "job_status",
+                "description": "Get the status of a background job, or of all jobs in the session if job_id is omitted",
+                "inputSchema": job_status_schema()
+            },
+            {
+                "name": "tail_job",
+                "description": "Get the output a background job has produced since the last tail_job call",
+                "inputSchema": tail_job_schema()
+            },
+            {
+                "name": "kill_job",
+                "description": "Kill a running background job",
+                "inputSchema": job_id_schema()
+            },
+            {
+                "name": "command_history",
+                "description": "List the commands run in this session with run_command, run_command_risky, run_test_command and run_checks, including those run after writes, with their exit codes, durations and working directories. Pass run_id to see the stored output of one run.",
+                "inputSchema": command_history_schema()
             }
         ]
     })
@@ -84,17 +129,60 @@ fn command_schema() -> Value {
             },
             "cwd": {
                 "type": "string",
-                "description": "Working directory for command execution (optional)"
+                "description": "Working directory for command execution, relative to the project root (optional, must be inside the project)"
             },
             "timeout": {
                 "type": "integer",
                 "description": "Command timeout in seconds (optional)"
+            },
+            "stdin": {
+                "type": "string",
+                "description": "Input written to the command's stdin, which is then closed (optional, stdin is empty otherwise)"
             }
         }
     })
 }
 
-fn session_only_schema() -> Value {
+fn await_approval_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id", "approval_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "approval_id": {
+                "type": "integer",
+                "description": "approval_id returned by run_command_risky",
+                "minimum": 1
+            }
+        }
+    })
+}
+
+fn start_job_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id", "command"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "command": {
+                "type": "string",
+                "description": "Command to execute"
+            },
+            "cwd": {
+                "type": "string",
+                "description": "Working directory for command execution, relative to the project root (optional, must be inside the project)"
+            }
+        }
+    })
+}
+
+fn job_status_schema() -> Value {
     json!({
         "type": "object",
         "required": ["session_id"],
@@ -102,6 +190,115 @@ fn session_only_schema() -> Value {
             "session_id": {
                 "type": "string",
                 "description": "Session ID for the project"
+            },
+            "job_id": {
+                "type": "string",
+                "description": "Job to query (optional, lists all jobs if omitted)"
+            }
+        }
+    })
+}
+
+fn tail_job_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id", "job_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "job_id": {
+                "type": "string",
+                "description": "Job to read output from"
+            },
+            "offset": {
+                "type": "integer",
+                "description": "Byte offset to read from instead of the end of the previous tail (optional, 0 rereads all output)",
+                "minimum": 0
+            }
+        }
+    })
+}
+
+fn command_history_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "run_id": {
+                "type": "integer",
+                "description": "Run to show the output of (optional, lists all runs if omitted)",
+                "minimum": 1
+            },
+            "offset": {
+                "type": "integer",
+                "description": "Byte offset into the run's output to start from (optional)",
+                "minimum": 0
+            }
+        }
+    })
+}
+
+fn job_id_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id", "job_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "job_id": {
+                "type": "string",
+                "description": "Job ID returned by start_job"
+            }
+        }
+    })
+}
+
+fn run_test_command_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "affected_only": {
+                "type": "boolean",
+                "description": "Only run the tests affected by files written in this session (default: false)"
+            },
+            "no_cache": {
+                "type": "boolean",
+                "description": "Run the tests even if no files changed since they last ran, instead of returning that result (default: false)"
+            }
+        }
+    })
+}
+
+fn run_checks_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["session_id"],
+        "properties": {
+            "session_id": {
+                "type": "string",
+                "description": "Session ID for the project"
+            },
+            "stages": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Only run the stages with these names (default: all stages)"
+            },
+            "stop_on_failure": {
+                "type": "boolean",
+                "description": "Skip the remaining stages once one fails (default: project setting)"
             }
         }
     })
@@ -1,8 +1,37 @@
 use serde::Deserialize;
 use serde_json::Value;
+use codem_core::types::PathFilter;
 
 #[derive(Deserialize)]
 pub struct ToolCall {
     pub name: String,
     pub arguments: Value,
-}
\ No newline at end of file
+    #[serde(rename = "_meta", default)]
+    pub meta: Option<Value>,
+}
+
+impl ToolCall {
+    /// Token the client asked us to attach to `notifications/progress` for this call
+    pub fn progress_token(&self) -> Option<&Value> {
+        self.meta.as_ref()
+            .and_then(|meta| meta.get("progressToken"))
+            .filter(|token| token.is_string() || token.is_number())
+    }
+}
+
+/// Include and exclude globs from a tool's arguments; the client makes them
+/// relative to the project
+pub fn path_filter(arguments: &Value) -> Option<PathFilter> {
+    let globs = |name: &str| -> Vec<String> {
+        arguments.get(name)
+            .and_then(|v| v.as_array())
+            .map(|globs| globs.iter().filter_map(|g| g.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+    let filter = PathFilter {
+        include: globs("include"),
+        exclude: globs("exclude"),
+        ..Default::default()
+    };
+    (!filter.is_empty()).then_some(filter)
+}
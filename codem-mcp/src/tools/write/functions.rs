@@ -4,6 +4,35 @@ use std::path::PathBuf;
 use crate::{server::Mcp, error::{error_response_with_content, get_error_content}};
 use codem_core::types::{WriteResultDetails, Change};
 
+/// Take the check and test output off the write details, as content items
+/// in the order they ran, and return what is left
+fn split_details(mut details: WriteResultDetails) -> (Vec<Value>, WriteResultDetails) {
+    let mut outputs = Vec::new();
+    loop {
+        details = match details {
+            WriteResultDetails::WithTestOutput { output, details } => {
+                outputs.push(json!({
+                    "type": "text",
+                    "text": format!("\nTest output:\n{}\n", output)
+                }));
+                *details
+            }
+            WriteResultDetails::WithCheckOutput { output, passed, details } => {
+                let heading = if passed { "Checks passed" } else { "Checks failed" };
+                outputs.push(json!({
+                    "type": "text",
+                    "text": format!("\n{}:\n{}\n", heading, output)
+                }));
+                *details
+            }
+            other => {
+                outputs.reverse();
+                return (outputs, other);
+            }
+        };
+    }
+}
+
 pub async fn write_new_file(mcp: &Mcp, session_id: &str, path: &str, content: &str, run_test: bool) -> Result<Value> {
     match mcp.client.write_new_file(session_id, &PathBuf::from(path), content, run_test).await {
         Ok(result) => {
@@ -17,12 +46,8 @@ pub async fn write_new_file(mcp: &Mcp, session_id: &str, path: &str, content: &s
                 )
             })];
 
-            if let WriteResultDetails::WithTestOutput { output, .. } = &result.details {
-                content.push(json!({
-                    "type": "text",
-                    "text": format!("\nTest output:\n{}\n", output)
-                }));
-            }
+            let (outputs, _) = split_details(result.details);
+            content.extend(outputs);
 
             Ok(json!({ "content": content }))
         },
@@ -43,12 +68,8 @@ pub async fn write_file_full(mcp: &Mcp, session_id: &str, path: &str, content: &
                 )
             })];
 
-            if let WriteResultDetails::WithTestOutput { output, .. } = &result.details {
-                content.push(json!({
-                    "type": "text",
-                    "text": format!("\nTest output:\n{}\n", output)
-                }));
-            }
+            let (outputs, _) = split_details(result.details);
+            content.extend(outputs);
 
             Ok(json!({ "content": content }))
         },
@@ -70,20 +91,10 @@ pub async fn write_file_small(mcp: &Mcp, session_id: &str, path: &str, changes:
             })];
             
             // Add change context information if available
-            let write_details = match result.details {
-                WriteResultDetails::None => None,
+            let (outputs, details) = split_details(result.details);
+            content.extend(outputs);
+            let write_details = match details {
                 WriteResultDetails::Partial(d) => Some(d),
-                WriteResultDetails::WithTestOutput { output, details: d } => {
-                    content.push(json!({
-                        "type": "text",
-                        "text": format!("\nTest output:\n{}\n", output)
-                    }));
-                    if let WriteResultDetails::Partial(d) = *d {
-                        Some(d)
-                    } else {
-                        None
-                    }
-                },
                 _ => None,
             };
             
@@ -121,20 +132,10 @@ pub async fn write_file_large(mcp: &Mcp, session_id: &str, path: &str, start_str
             })];
             
             // Add change context information if available
-            let write_details = match result.details {
-                WriteResultDetails::None => None,
+            let (outputs, details) = split_details(result.details);
+            content.extend(outputs);
+            let write_details = match details {
                 WriteResultDetails::PartialLarge(d) => Some(d),
-                WriteResultDetails::WithTestOutput { output, details: d } => {
-                    content.push(json!({
-                        "type": "text",
-                        "text": format!("\nTest output:\n{}\n", output)
-                    }));
-                    if let WriteResultDetails::PartialLarge(d) = *d {
-                        Some(d)
-                    } else {
-                        None
-                    }
-                },
                 _ => None,
             };
             